@@ -12,15 +12,19 @@ use crate::connection_pool::{ConnectionPool, McpServerConfig};
 use crate::error::Error;
 use crate::prompts;
 
+use super::sampling::SamplingHandler;
 use super::types::{
     Agent, AgentConfig, AgentContext, AgentState, AgentMessage, MessageType, 
-    ToolCall
+    SamplingApproval, ToolCall, ToolCallStatus, ToolInfo
 };
 
 // HTTP客户端用于直接调用DeepSeek API
 use reqwest::Client;
 use serde_json::json;
 
+/// 处理一条输入时最多调用模型的轮数
+const MAX_ITERATIONS: usize = 10;
+
 /// DeepSeek API 适配器
 /// 保留连接池作为MCP配置源，直接使用DeepSeek API处理所有AI交互
 pub struct Adapter {
@@ -48,6 +52,16 @@ impl Adapter {
         // 创建HTTP客户端
         let http_client = Client::new();
         
+        // 允许MCP服务器通过采样请求借用DeepSeek模型
+        if !matches!(config.sampling.approval, SamplingApproval::Deny) {
+            connection_pool
+                .set_client_handler_factory(SamplingHandler::factory(
+                    config.deepseek.clone(),
+                    config.sampling.clone(),
+                ))
+                .await;
+        }
+        
         // 创建简化的智能体上下文
         let context = Arc::new(tokio::sync::RwLock::new(AgentContext {
             state: AgentState::Idle,
@@ -90,6 +104,11 @@ impl Adapter {
                         directory: config.get("directory")
                             .and_then(|d| d.as_str())
                             .map(|s| s.to_string()),
+                        url: config.get("url")
+                            .and_then(|u| u.as_str())
+                            .map(|s| s.to_string()),
+                        log_level: config.get("logLevel")
+                            .and_then(|l| serde_json::from_value(l.clone()).ok()),
                     };
                     
                     self.connection_pool.register_server(name.clone(), server_config).await;
@@ -103,6 +122,23 @@ impl Adapter {
         Ok(())
     }
     
+    /// 发现所有已注册服务器提供的工具（自动跟随分页游标），返回发现的工具数量
+    pub async fn discover_tools(&self) -> usize {
+        let tools = self.connection_pool.list_all_tools().await;
+        let mut context = self.context.write().await;
+        context.available_tools.clear();
+        for (server, tool) in tools {
+            let tool_info = ToolInfo {
+                name: tool.name.clone(),
+                description: tool.description.unwrap_or_default(),
+                input_schema: tool.input_schema,
+                server,
+            };
+            context.available_tools.insert(tool.name, tool_info);
+        }
+        context.available_tools.len()
+    }
+    
     /// 构建系统提示（简化版）
     async fn build_system_prompt(&self) -> String {
         let context = self.context.read().await;
@@ -113,32 +149,43 @@ impl Adapter {
         prompts::get_mcp_system_prompt(&workspace_root)
     }
     
-    /// 直接使用DeepSeek API进行查询（核心功能）
-    async fn query_with_deepseek(&self, prompt: &str) -> Result<String, Error> {
-        // 构建系统提示
-        let system_prompt = self.build_system_prompt().await;
-        
+    /// 构建发送给模型的工具定义（function calling 格式）
+    async fn tools_schema(&self) -> Vec<serde_json::Value> {
+        let context = self.context.read().await;
+        context.available_tools
+            .values()
+            .map(|tool| json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.input_schema
+                }
+            }))
+            .collect()
+    }
+    
+    /// 调用DeepSeek对话接口，返回模型回复的消息
+    async fn chat_completion(
+        &self,
+        messages: &[serde_json::Value],
+        tools: &[serde_json::Value],
+    ) -> Result<serde_json::Value, Error> {
         // 构建请求体
-        let request_body = json!({
+        let mut request_body = json!({
             "model": self.config.deepseek.model,
-            "messages": [
-                {
-                    "role": "system",
-                    "content": system_prompt
-                },
-                {
-                    "role": "user", 
-                    "content": prompt
-                }
-            ],
+            "messages": messages,
             "max_tokens": self.config.deepseek.max_tokens,
             "temperature": self.config.deepseek.temperature,
             "stream": false
         });
+        if !tools.is_empty() {
+            request_body["tools"] = json!(tools);
+        }
         
         // 发送请求到DeepSeek API
         let response = self.http_client
-            .post(&format!("{}/v1/chat/completions", self.config.deepseek.base_url))
+            .post(format!("{}/v1/chat/completions", self.config.deepseek.base_url))
             .header("Authorization", format!("Bearer {}", self.config.deepseek.api_key))
             .header("Content-Type", "application/json")
             .json(&request_body)
@@ -157,18 +204,47 @@ impl Adapter {
         let response_json: serde_json::Value = response.json().await
             .map_err(|e| Error::Other(format!("响应解析失败: {}", e)))?;
         
-        // 提取回复内容
-        if let Some(choices) = response_json.get("choices").and_then(|c| c.as_array()) {
-            if let Some(first_choice) = choices.first() {
-                if let Some(message) = first_choice.get("message") {
-                    if let Some(content) = message.get("content").and_then(|c| c.as_str()) {
-                        return Ok(content.to_string());
-                    }
-                }
-            }
-        }
+        // 提取回复消息
+        response_json
+            .get("choices")
+            .and_then(|choices| choices.get(0))
+            .and_then(|choice| choice.get("message"))
+            .cloned()
+            .ok_or_else(|| Error::Other("API响应格式不正确".to_string()))
+    }
+    
+    /// 执行模型请求的一次工具调用，返回调用记录和交给模型的结构化结果
+    async fn run_tool_call(&mut self, tool_call: &serde_json::Value) -> (ToolCall, serde_json::Value) {
+        let name = tool_call["function"]["name"].as_str().unwrap_or_default().to_string();
+        let arguments = tool_call["function"]["arguments"].as_str().unwrap_or("{}");
+        let arguments: HashMap<String, serde_json::Value> = if arguments.trim().is_empty() {
+            HashMap::new()
+        } else {
+            serde_json::from_str(arguments).unwrap_or_else(|e| {
+                warn!("工具调用参数解析失败: {}, 原始参数: {}", e, arguments);
+                HashMap::new()
+            })
+        };
+        let mut call = ToolCall {
+            name: name.clone(),
+            arguments,
+            call_id: tool_call["id"].as_str().unwrap_or_default().to_string(),
+            status: ToolCallStatus::Executing,
+        };
         
-        Err(Error::Other("API响应格式不正确".to_string()))
+        self.context.write().await.state = AgentState::ExecutingTool(name.clone());
+        // 结构化结果原样以JSON交给模型
+        let result = match self.execute_tool(&call).await {
+            Ok(result) => {
+                call.status = ToolCallStatus::Success;
+                json!({"tool": name, "success": true, "result": result})
+            }
+            Err(e) => {
+                call.status = ToolCallStatus::Failed(e.to_string());
+                json!({"tool": name, "success": false, "error": e.to_string()})
+            }
+        };
+        (call, result)
     }
 }
 
@@ -177,9 +253,13 @@ impl Agent for Adapter {
     async fn initialize(&mut self) -> Result<(), Error> {
         tracing::info!("初始化DeepSeek API适配器...");
         
-        // 注册服务器配置（仅用于配置，不实际连接）
+        // 注册服务器配置
         self.register_server_configs().await?;
         
+        // 发现服务器提供的工具
+        let tool_count = self.discover_tools().await;
+        info!("发现 {} 个MCP工具", tool_count);
+        
         // 更新状态
         {
             let mut context = self.context.write().await;
@@ -192,10 +272,10 @@ impl Agent for Adapter {
     }
     
     async fn process_input(&mut self, input: &str) -> Result<String, Error> {
-        self.process_input_with_iterations(input, 1).await
+        self.process_input_with_iterations(input, MAX_ITERATIONS).await
     }
 
-    async fn process_input_with_iterations(&mut self, input: &str, _max_iterations: usize) -> Result<String, Error> {
+    async fn process_input_with_iterations(&mut self, input: &str, max_iterations: usize) -> Result<String, Error> {
         tracing::info!("处理用户输入: {}", input);
         
         // 更新状态
@@ -205,33 +285,76 @@ impl Agent for Adapter {
             context.state = AgentState::Thinking;
         }
         
-        // 使用DeepSeek API进行查询
-        let response = self.query_with_deepseek(input).await?;
+        let mut messages = vec![
+            json!({"role": "system", "content": self.build_system_prompt().await}),
+            json!({"role": "user", "content": input}),
+        ];
+        let tools = self.tools_schema().await;
+        let mut executed_tool_calls = Vec::new();
         
-        // 更新状态和消息历史
-        {
-            let mut context = self.context.write().await;
-            context.state = AgentState::Idle;
+        for iteration in 1..=max_iterations {
+            self.context.write().await.state = AgentState::WaitingForAPI;
+            let message = self.chat_completion(&messages, &tools).await?;
             
-            let agent_message = AgentMessage {
-                id: uuid::Uuid::new_v4().to_string(),
-                message_type: MessageType::AgentResponse,
-                content: response.clone(),
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-                tool_calls: Vec::new(),
-            };
-            context.message_history.push(agent_message);
+            let tool_calls = message
+                .get("tool_calls")
+                .and_then(|calls| calls.as_array())
+                .cloned()
+                .unwrap_or_default();
+            if tool_calls.is_empty() {
+                let response = message
+                    .get("content")
+                    .and_then(|content| content.as_str())
+                    .ok_or_else(|| Error::Other("API响应格式不正确".to_string()))?
+                    .to_string();
+                
+                // 更新状态和消息历史
+                let mut context = self.context.write().await;
+                context.state = AgentState::Idle;
+                context.message_history.push(AgentMessage {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    message_type: MessageType::AgentResponse,
+                    content: response.clone(),
+                    timestamp: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs(),
+                    tool_calls: executed_tool_calls,
+                });
+                return Ok(response);
+            }
+            
+            // 执行工具调用，结果以 `tool` 消息交给模型进入下一轮
+            tracing::info!("第 {} 轮，模型请求 {} 个工具调用", iteration, tool_calls.len());
+            messages.push(message);
+            for tool_call in &tool_calls {
+                let (call, result) = self.run_tool_call(tool_call).await;
+                messages.push(json!({
+                    "role": "tool",
+                    "tool_call_id": call.call_id,
+                    "content": serde_json::to_string(&result)?
+                }));
+                executed_tool_calls.push(call);
+            }
         }
         
-        Ok(response)
+        self.context.write().await.state = AgentState::Idle;
+        Ok(format!("经过 {} 轮尝试，仍然无法完全解决您的问题。", max_iterations))
     }
     
-    async fn execute_tool(&mut self, _tool_call: &ToolCall) -> Result<serde_json::Value, Error> {
-        // 工具调用由连接池处理
-        Err(Error::Other("工具调用由连接池处理".to_string()))
+    async fn execute_tool(&mut self, tool_call: &ToolCall) -> Result<serde_json::Value, Error> {
+        let server = self.context.read().await
+            .available_tools
+            .get(&tool_call.name)
+            .map(|tool| tool.server.clone())
+            .ok_or_else(|| Error::Other(format!("未知工具: {}", tool_call.name)))?;
+        
+        let arguments = serde_json::to_value(&tool_call.arguments)?;
+        let result = self.connection_pool
+            .call_tool(&server, &tool_call.name, arguments)
+            .await
+            .map_err(|e| Error::Other(e.to_string()))?;
+        Ok(result.to_json())
     }
     
     fn get_state(&self) -> &AgentState {
@@ -251,3 +374,197 @@ impl Agent for Adapter {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::types::{BehaviorConfig, DeepSeekConfig, ToolStrategy, WorkspaceConfig};
+    use crate::server::{ServerHandler, Server};
+    use crate::transport::socket::TcpTransport;
+    use crate::types::{ClientCapabilities, Implementation, ServerCapabilities, ToolsCapability};
+
+    /// 分两页返回工具的测试服务器
+    struct PagedToolServer;
+
+    #[async_trait]
+    impl ServerHandler for PagedToolServer {
+        async fn initialize(
+            &self,
+            _implementation: Implementation,
+            _capabilities: ClientCapabilities,
+        ) -> Result<ServerCapabilities, Error> {
+            Ok(ServerCapabilities {
+                tools: Some(ToolsCapability::default()),
+                ..Default::default()
+            })
+        }
+
+        async fn shutdown(&self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn handle_method(
+            &self,
+            method: &str,
+            params: Option<serde_json::Value>,
+        ) -> Result<serde_json::Value, Error> {
+            let tool = |name: &str| json!({"name": name, "inputSchema": {"type": "object"}});
+            match method {
+                "tools/list" => match params.as_ref().and_then(|p| p.get("cursor")) {
+                    None => Ok(json!({"tools": [tool("get_balance")], "nextCursor": "page-2"})),
+                    Some(_) => Ok(json!({"tools": [tool("send_transaction")]})),
+                },
+                "tools/call" => {
+                    let address = params.unwrap_or_default()["arguments"]["address"].clone();
+                    Ok(json!({
+                        "content": [{"type": "text", "text": "1.5 ETH"}],
+                        "structuredContent": {"address": address, "balance": 1.5, "symbol": "ETH"}
+                    }))
+                }
+                _ => Err(Error::protocol(
+                    crate::error::ErrorCode::MethodNotFound,
+                    method.to_string(),
+                )),
+            }
+        }
+    }
+
+    /// 在本地TCP端口上启动测试服务器，返回其地址
+    async fn spawn_tool_server() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let transport = TcpTransport::from_stream(stream).unwrap();
+                let server = Server::new(Arc::new(transport), Arc::new(PagedToolServer));
+                tokio::spawn(async move { server.start().await });
+            }
+        });
+        format!("tcp://{}", addr)
+    }
+
+    fn test_config(base_url: &str) -> AgentConfig {
+        AgentConfig {
+            deepseek: DeepSeekConfig {
+                base_url: base_url.to_string(),
+                api_key: "test-key".to_string(),
+                model: "deepseek-chat".to_string(),
+                max_tokens: 2000,
+                temperature: 0.7,
+            },
+            behavior: BehaviorConfig {
+                max_retries: 0,
+                timeout_seconds: 30,
+                verbose_logging: false,
+                tool_strategy: ToolStrategy::Auto,
+            },
+            workspace: WorkspaceConfig {
+                directories: Vec::new(),
+                smart_detection: false,
+                exclude_patterns: Vec::new(),
+            },
+            sampling: Default::default(),
+        }
+    }
+
+    /// 模拟DeepSeek接口：第一轮请求调用 `get_balance`，收到工具结果后把请求体记录到 `requests` 并结束对话
+    fn spawn_llm(requests: Arc<std::sync::Mutex<Vec<serde_json::Value>>>) -> String {
+        use warp::Filter;
+
+        let route = warp::post()
+            .and(warp::path!("v1" / "chat" / "completions"))
+            .and(warp::body::json())
+            .map(move |body: serde_json::Value| {
+                requests.lock().unwrap().push(body.clone());
+                let last = body["messages"].as_array().unwrap().last().unwrap().clone();
+                let message = if last["role"] == "tool" {
+                    json!({"role": "assistant", "content": "余额为 1.5 ETH"})
+                } else {
+                    json!({
+                        "role": "assistant",
+                        "content": "",
+                        "tool_calls": [{
+                            "id": "call-1",
+                            "type": "function",
+                            "function": {"name": "get_balance", "arguments": "{\"address\":\"0x1\"}"}
+                        }]
+                    })
+                };
+                warp::reply::json(&json!({"choices": [{"message": message}]}))
+            });
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        format!("http://{}", addr)
+    }
+
+    /// 创建连接到测试服务器的适配器
+    async fn adapter_with_tool_server(base_url: &str) -> Adapter {
+        let pool = Arc::new(ConnectionPool::new());
+        let server_config = McpServerConfig {
+            command: String::new(),
+            args: Vec::new(),
+            directory: None,
+            env: None,
+            url: Some(spawn_tool_server().await),
+            log_level: None,
+        };
+        pool.register_server("wallet".to_string(), server_config).await;
+        Adapter::with_connection_pool(test_config(base_url), pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_discover_tools_follows_cursor() {
+        let adapter = adapter_with_tool_server("http://127.0.0.1:9").await;
+        assert_eq!(adapter.discover_tools().await, 2);
+
+        let context = adapter.context.read().await;
+        let mut names: Vec<_> = context.available_tools.keys().cloned().collect();
+        names.sort();
+        assert_eq!(names, ["get_balance", "send_transaction"]);
+        assert_eq!(context.available_tools["send_transaction"].server, "wallet");
+    }
+
+    #[tokio::test]
+    async fn test_tool_results_reach_model_as_json() {
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut adapter = adapter_with_tool_server(&spawn_llm(requests.clone())).await;
+        adapter.discover_tools().await;
+
+        let response = adapter.process_input("查询0x1的余额").await.unwrap();
+        assert_eq!(response, "余额为 1.5 ETH");
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        // 第一轮请求附带了发现的工具
+        assert_eq!(requests[0]["tools"].as_array().unwrap().len(), 2);
+
+        // 第二轮请求中的工具结果是结构化JSON，而不是格式化文本
+        let messages = requests[1]["messages"].as_array().unwrap();
+        assert_eq!(messages[2]["tool_calls"][0]["id"], "call-1");
+        let tool_message = &messages[3];
+        assert_eq!(tool_message["role"], "tool");
+        assert_eq!(tool_message["tool_call_id"], "call-1");
+        let content: serde_json::Value =
+            serde_json::from_str(tool_message["content"].as_str().unwrap()).unwrap();
+        assert_eq!(
+            content,
+            json!({
+                "tool": "get_balance",
+                "success": true,
+                "result": {"address": "0x1", "balance": 1.5, "symbol": "ETH"}
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_tool_reported_to_model() {
+        let mut adapter = adapter_with_tool_server("http://127.0.0.1:9").await;
+        let (call, result) = adapter
+            .run_tool_call(&json!({"id": "call-2", "function": {"name": "missing", "arguments": ""}}))
+            .await;
+        assert!(matches!(call.status, ToolCallStatus::Failed(_)));
+        assert_eq!(result["tool"], "missing");
+        assert_eq!(result["success"], false);
+        assert!(result["error"].as_str().unwrap().contains("未知工具"));
+    }
+}
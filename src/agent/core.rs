@@ -2,12 +2,12 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::path::PathBuf;
 use async_trait::async_trait;
+use futures::StreamExt;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 use tracing::{info, warn};
 
 use crate::connection_pool::{ConnectionPool, McpServerConfig};
-use crate::protocol::Request;
 use crate::error::Error;
 
 use super::types::{
@@ -93,6 +93,11 @@ impl McpAgent {
                         directory: config.get("directory")
                             .and_then(|d| d.as_str())
                             .map(|s| s.to_string()),
+                        url: config.get("url")
+                            .and_then(|u| u.as_str())
+                            .map(|s| s.to_string()),
+                        log_level: config.get("logLevel")
+                            .and_then(|l| serde_json::from_value(l.clone()).ok()),
                     };
                     
                     self.connection_pool.register_server(name.clone(), server_config).await;
@@ -127,42 +132,28 @@ impl McpAgent {
             
             for server_name in &servers {
                 if let Ok(connection) = connection_pool.get_connection(server_name).await {
-                    let client = connection.lock().await;
+                    let client = &connection;
                     
-                    // 获取工具列表
-                    match client.request("tools/list", None).await {
-                        Ok(result) => {
-                            if let Some(tools) = result.get("tools").and_then(|t| t.as_array()) {
-                                let tool_count = tools.len();
-                                server_tool_counts.insert(server_name.clone(), tool_count);
-                                
-                                // 将工具添加到上下文中
-                                {
-                                    let mut ctx = context.write().await;
-                                    for tool in tools {
-                                        if let (Some(name), Some(description), Some(input_schema)) = (
-                                            tool.get("name").and_then(|n| n.as_str()),
-                                            tool.get("description").and_then(|d| d.as_str()),
-                                            tool.get("inputSchema")
-                                        ) {
-                                            let tool_info = ToolInfo {
-                                                name: name.to_string(),
-                                                description: description.to_string(),
-                                                input_schema: input_schema.clone(),
-                                                server: server_name.clone(),
-                                            };
-                                            ctx.available_tools.insert(name.to_string(), tool_info);
-                                        }
-                                    }
-                                }
-                                
-                                // 静默加载，不输出日志
-                            }
-                        }
-                        Err(_) => {
+                    // 获取工具列表（自动跟随分页游标）
+                    let mut tools = client.list_tools_paginated();
+                    let mut tool_count = 0;
+                    while let Some(tool) = tools.next().await {
+                        let Ok(tool) = tool else {
                             // 静默处理错误，不输出日志
-                        }
+                            break;
+                        };
+                        tool_count += 1;
+
+                        // 将工具添加到上下文中
+                        let tool_info = ToolInfo {
+                            name: tool.name.clone(),
+                            description: tool.description.unwrap_or_default(),
+                            input_schema: tool.input_schema,
+                            server: server_name.clone(),
+                        };
+                        context.write().await.available_tools.insert(tool.name, tool_info);
                     }
+                    server_tool_counts.insert(server_name.clone(), tool_count);
                 }
             }
             
@@ -182,37 +173,28 @@ impl McpAgent {
         
         for server_name in &servers {
             if let Ok(connection) = self.connection_pool.get_connection(server_name).await {
-                let client = connection.lock().await;
+                let client = &connection;
                 
-                // 获取工具列表
-                match client.request("tools/list", None).await {
-                    Ok(result) => {
-                        if let Some(tools) = result.get("tools").and_then(|t| t.as_array()) {
-                                let tool_count = tools.len();
-                            server_tool_counts.insert(server_name.clone(), tool_count);
-                                
-                            // 将工具添加到上下文中
-                                for tool in tools {
-                                if let (Some(name), Some(description), Some(input_schema)) = (
-                                        tool.get("name").and_then(|n| n.as_str()),
-                                    tool.get("description").and_then(|d| d.as_str()),
-                                    tool.get("inputSchema")
-                                    ) {
-                                        let tool_info = ToolInfo {
-                                            name: name.to_string(),
-                                            description: description.to_string(),
-                                        input_schema: input_schema.clone(),
-                                        server: server_name.clone(),
-                                        };
-                                        context.available_tools.insert(name.to_string(), tool_info);
-                                }
-                            }
-                        }
-                    }
-                    Err(_) => {
+                // 获取工具列表（自动跟随分页游标）
+                let mut tools = client.list_tools_paginated();
+                let mut tool_count = 0;
+                while let Some(tool) = tools.next().await {
+                    let Ok(tool) = tool else {
                         // 静默处理错误
-                    }
+                        break;
+                    };
+                    tool_count += 1;
+
+                    // 将工具添加到上下文中
+                    let tool_info = ToolInfo {
+                        name: tool.name.clone(),
+                        description: tool.description.unwrap_or_default(),
+                        input_schema: tool.input_schema,
+                        server: server_name.clone(),
+                    };
+                    context.available_tools.insert(tool.name, tool_info);
                 }
+                server_tool_counts.insert(server_name.clone(), tool_count);
             }
         }
         
@@ -524,14 +506,12 @@ impl Agent for McpAgent {
                             // 执行工具
                             match self.execute_tool(&tool_call_info).await {
                                 Ok(result) => {
-                                    // 将工具执行结果转换为字符串
-                                    let result_str = if result.is_string() {
-                                        result.as_str().unwrap_or("").to_string()
-                                    } else {
-                                        serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string())
-                                    };
-                                    
-                                    tool_results.push(format!("工具 {} 执行成功，结果：{}", name, result_str));
+                                    // 结构化结果原样以JSON交给模型
+                                    tool_results.push(serde_json::json!({
+                                        "tool": name,
+                                        "success": true,
+                                        "result": result,
+                                    }));
                                     executed_tool_calls.push(ToolCall {
                                         name: name.clone(),
                                         arguments: tool_call_info.arguments,
@@ -540,7 +520,11 @@ impl Agent for McpAgent {
                                     });
                                 }
                                 Err(e) => {
-                                    tool_results.push(format!("工具 {} 执行失败: {}", name, e));
+                                    tool_results.push(serde_json::json!({
+                                        "tool": name,
+                                        "success": false,
+                                        "error": e.to_string(),
+                                    }));
                                     executed_tool_calls.push(ToolCall {
                                         name: name.clone(),
                                         arguments: tool_call_info.arguments,
@@ -560,7 +544,7 @@ impl Agent for McpAgent {
                             let tool_result_message = AgentMessage {
                                 id: Uuid::new_v4().to_string(),
                                 message_type: MessageType::ToolResult,
-                                content: tool_results.join("\n"),
+                                content: serde_json::to_string(&tool_results).unwrap_or_default(),
                                 timestamp: std::time::SystemTime::now()
                                     .duration_since(std::time::UNIX_EPOCH)
                                     .unwrap()
@@ -586,10 +570,9 @@ impl Agent for McpAgent {
                                 .join("\n");
                             
                             let successful_results = tool_results.iter()
-                                .filter(|result| result.contains("执行成功"))
-                                .cloned()
-                                .collect::<Vec<_>>()
-                                .join("\n");
+                                .filter(|result| result["success"] == true)
+                                .collect::<Vec<_>>();
+                            let successful_results = serde_json::to_string(&successful_results).unwrap_or_default();
                             
                             current_input = format!(
                                 "刚才的工具调用结果：
@@ -633,7 +616,7 @@ impl Agent for McpAgent {
                                 return Ok(response_content);
                             } else {
                                 // 任务未完成，继续执行，但要告知AI前面的工具执行结果
-                                let all_results = tool_results.join("\n");
+                                let all_results = serde_json::to_string(&tool_results).unwrap_or_default();
                                 current_input = format!(
                                     "刚才的工具执行结果：
 {}
@@ -707,23 +690,11 @@ impl Agent for McpAgent {
                 .clone()
         };
         
-        // 获取连接
-        let connection = self.connection_pool.get_connection(&tool_info.server).await?;
-        let client = connection.lock().await;
-        
-        // 构建工具调用请求
-        let request = Request {
-            jsonrpc: "2.0".to_string(),
-            id: crate::protocol::RequestId::String(tool_call.call_id.clone()),
-            method: "tools/call".to_string(),
-            params: Some(serde_json::json!({
-                "name": tool_call.name,
-                "arguments": tool_call.arguments
-            })),
-        };
-        
-        // 发送请求
-        let response = client.request(&request.method, request.params).await?;
+        // 通过连接池调用工具，执行期间的进度会广播给订阅者（如CLI进度条）
+        let result = self.connection_pool
+            .call_tool(&tool_info.server, &tool_call.name, serde_json::json!(tool_call.arguments))
+            .await?;
+        let response = result.to_json();
         
         // 更新状态
         {
@@ -0,0 +1,379 @@
+// ============================================
+// 将智能体暴露为MCP服务器
+// 其他MCP宿主（IDE、其他智能体）可通过 `chat` 工具把Alou当作子智能体驱动，
+// 也可直接调用连接池中各服务器的工具
+// ============================================
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde_json::json;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::connection_pool::ConnectionPool;
+use crate::error::{Error, ErrorCode};
+use crate::server::{ProgressReporter, RequestContext, ServerHandler};
+use crate::types::{
+    CallToolRequest, CallToolResult, ClientCapabilities, Implementation, ListToolsResult,
+    MessageContent, ServerCapabilities, Tool, ToolsCapability,
+};
+
+use super::types::Agent;
+
+/// 与智能体对话的工具名称
+pub const CHAT_TOOL: &str = "chat";
+
+/// 下游工具名称中服务器名与工具名之间的分隔符，如 `filesystem__read_file`
+pub const TOOL_NAME_SEPARATOR: &str = "__";
+
+/// 以MCP服务器形式暴露智能体的处理器
+pub struct AgentServer<A> {
+    /// 被包装的智能体，同一时间只处理一条消息
+    agent: Mutex<A>,
+    /// 智能体使用的连接池，其中各服务器的工具一并对外暴露
+    connection_pool: Arc<ConnectionPool>,
+    /// 最近一次列出的带前缀工具名到 `(服务器名, 工具名)` 的映射
+    ///
+    /// 服务器名本身可能含有分隔符，不能靠拆分名称找回服务器
+    tool_routes: Mutex<HashMap<String, (String, String)>>,
+}
+
+impl<A: Agent> AgentServer<A> {
+    /// 创建新的服务器处理器，`agent` 应已完成初始化
+    pub fn new(agent: A, connection_pool: Arc<ConnectionPool>) -> Self {
+        Self {
+            agent: Mutex::new(agent),
+            connection_pool,
+            tool_routes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `chat` 工具定义
+    fn chat_tool() -> Tool {
+        Tool {
+            name: CHAT_TOOL.to_string(),
+            title: Some("与Alou对话".to_string()),
+            description: Some("向Alou智能助手发送一条消息并返回它的回复".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "message": {"type": "string", "description": "发给智能体的消息"}
+                },
+                "required": ["message"]
+            }),
+            output_schema: None,
+            annotations: None,
+        }
+    }
+
+    /// 列出对外暴露的所有工具：`chat` 加上各服务器的工具（名称带服务器前缀）
+    async fn list_tools(&self) -> ListToolsResult {
+        let mut tools = vec![Self::chat_tool()];
+        let mut routes = HashMap::new();
+        for (server_name, mut tool) in self.connection_pool.list_all_tools().await {
+            let name = format!("{}{}{}", server_name, TOOL_NAME_SEPARATOR, tool.name);
+            let tool_name = std::mem::replace(&mut tool.name, name.clone());
+            if let Some((other, _)) = routes.insert(name.clone(), (server_name.clone(), tool_name)) {
+                // 如服务器 `a` 的工具 `b__c` 与服务器 `a__b` 的工具 `c`
+                warn!("工具名称 {} 同时属于服务器 {} 和 {}，只保留后者", name, other, server_name);
+                tools.retain(|t: &Tool| t.name != name);
+            }
+            tools.push(tool);
+        }
+        *self.tool_routes.lock().await = routes;
+        ListToolsResult {
+            tools,
+            next_cursor: None,
+        }
+    }
+
+    /// 查找带前缀工具名对应的 `(服务器名, 工具名)`
+    ///
+    /// 客户端可能未先列出工具就直接调用，映射中找不到时重新列出一次
+    async fn resolve_tool(&self, name: &str) -> Result<(String, String), Error> {
+        if let Some(route) = self.tool_routes.lock().await.get(name) {
+            return Ok(route.clone());
+        }
+        self.list_tools().await;
+        self.tool_routes
+            .lock()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::protocol(ErrorCode::InvalidParams, format!("未知工具: {}", name)))
+    }
+
+    /// 调用工具；执行失败时返回 `isError` 结果而不是协议错误
+    async fn call_tool(&self, request: CallToolRequest) -> Result<CallToolResult, Error> {
+        let result = if request.name == CHAT_TOOL {
+            let message = request
+                .arguments
+                .get("message")
+                .and_then(|m| m.as_str())
+                .ok_or_else(|| Error::protocol(ErrorCode::InvalidParams, "缺少参数: message"))?;
+            info!("收到MCP对话请求: {}", message);
+            self.agent
+                .lock()
+                .await
+                .process_input(message)
+                .await
+                .map(|response| CallToolResult {
+                    content: vec![MessageContent::text(response)],
+                    structured_content: None,
+                    is_error: false,
+                })
+                .map_err(|e| e.to_string())
+        } else {
+            let (server_name, tool_name) = self.resolve_tool(&request.name).await?;
+            self.connection_pool
+                .call_tool(&server_name, &tool_name, request.arguments)
+                .await
+                .map_err(|e| e.to_string())
+        };
+
+        Ok(result.unwrap_or_else(|e| CallToolResult {
+            content: vec![MessageContent::text(e)],
+            structured_content: None,
+            is_error: true,
+        }))
+    }
+
+    /// 在 `work` 执行期间把连接池中工具的进度转发给客户端
+    async fn forward_progress<T>(
+        &self,
+        work: impl Future<Output = T>,
+        progress: Option<ProgressReporter>,
+    ) -> T {
+        let Some(progress) = progress else {
+            return work.await;
+        };
+        let mut updates = self.connection_pool.subscribe_tool_progress();
+        tokio::pin!(work);
+        // 下游进度来自不同工具，用已收到的更新数作为递增的进度值
+        let mut count = 0.0;
+        loop {
+            tokio::select! {
+                result = &mut work => return result,
+                Ok(update) = updates.recv() => {
+                    count += 1.0;
+                    let message = format!(
+                        "{}{}{}: {}",
+                        update.server_name,
+                        TOOL_NAME_SEPARATOR,
+                        update.tool_name,
+                        update.progress.message.unwrap_or_default()
+                    );
+                    if let Err(e) = progress.report(count, None, Some(message)).await {
+                        debug!("转发工具进度失败: {}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<A: Agent + 'static> ServerHandler for AgentServer<A> {
+    async fn initialize(
+        &self,
+        implementation: Implementation,
+        _capabilities: ClientCapabilities,
+    ) -> Result<ServerCapabilities, Error> {
+        info!("MCP客户端已连接: {} {}", implementation.name, implementation.version);
+        Ok(ServerCapabilities {
+            tools: Some(ToolsCapability::default()),
+            ..Default::default()
+        })
+    }
+
+    async fn shutdown(&self) -> Result<(), Error> {
+        self.connection_pool
+            .close_all_connections()
+            .await
+            .map_err(|e| Error::Other(e.to_string()))
+    }
+
+    fn server_info(&self) -> Implementation {
+        Implementation {
+            name: "alou".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            title: Some("Alou智能助手".to_string()),
+        }
+    }
+
+    fn instructions(&self) -> Option<String> {
+        Some(format!(
+            "使用 `{}` 工具与Alou对话；其他工具名称形如 `服务器{}工具`，直接调用对应MCP服务器的工具",
+            CHAT_TOOL, TOOL_NAME_SEPARATOR
+        ))
+    }
+
+    async fn handle_method(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, Error> {
+        match method {
+            "tools/list" => Ok(serde_json::to_value(self.list_tools().await)?),
+            "tools/call" => {
+                let request: CallToolRequest = serde_json::from_value(params.unwrap_or_default())
+                    .map_err(|e| Error::protocol(ErrorCode::InvalidParams, e.to_string()))?;
+                Ok(serde_json::to_value(self.call_tool(request).await?)?)
+            }
+            _ => Err(Error::protocol(
+                ErrorCode::MethodNotFound,
+                format!("Method not found: {}", method),
+            )),
+        }
+    }
+
+    async fn handle_method_with_context(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        context: RequestContext,
+    ) -> Result<serde_json::Value, Error> {
+        self.forward_progress(self.handle_method(method, params), context.progress)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::types::{AgentContext, AgentState, ToolCall};
+    use crate::client::Client;
+    use crate::server::Server;
+    use crate::transport::memory::duplex;
+    use crate::workspace_context::WorkspaceContextFactory;
+    use std::collections::HashMap;
+
+    /// 原样返回输入的智能体，输入 `fail` 时报错
+    struct EchoAgent {
+        context: AgentContext,
+    }
+
+    impl EchoAgent {
+        fn new() -> Self {
+            Self {
+                context: AgentContext {
+                    state: AgentState::Idle,
+                    message_history: Vec::new(),
+                    workspace_context: WorkspaceContextFactory::create_basic(),
+                    available_tools: HashMap::new(),
+                    current_task: None,
+                },
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Agent for EchoAgent {
+        async fn initialize(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn process_input(&mut self, input: &str) -> Result<String, Error> {
+            self.process_input_with_iterations(input, 1).await
+        }
+
+        async fn process_input_with_iterations(
+            &mut self,
+            input: &str,
+            _max_iterations: usize,
+        ) -> Result<String, Error> {
+            match input {
+                "fail" => Err(Error::Other("处理失败".to_string())),
+                _ => Ok(format!("回复: {}", input)),
+            }
+        }
+
+        async fn execute_tool(&mut self, _tool_call: &ToolCall) -> Result<serde_json::Value, Error> {
+            Err(Error::Other("不支持工具调用".to_string()))
+        }
+
+        fn get_state(&self) -> &AgentState {
+            &self.context.state
+        }
+
+        fn get_context(&self) -> &AgentContext {
+            &self.context
+        }
+
+        async fn reset(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_tool() {
+        let (server_transport, client_transport) = duplex();
+        let handler = AgentServer::new(EchoAgent::new(), Arc::new(ConnectionPool::new()));
+        let server = Server::new(Arc::new(server_transport), Arc::new(handler));
+        let server_handle = tokio::spawn(async move { server.start().await });
+
+        let client = Client::new(Arc::new(client_transport), None);
+        let init = client
+            .initialize(
+                Implementation {
+                    name: "test-host".to_string(),
+                    version: "0.1.0".to_string(),
+                    title: None,
+                },
+                ClientCapabilities::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(init.server_info.name, "alou");
+
+        let tools = client.list_tools().await.unwrap().tools;
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, CHAT_TOOL);
+
+        let result = client
+            .call_tool(CHAT_TOOL, json!({"message": "你好"}))
+            .await
+            .unwrap();
+        assert_eq!(result.text(), "回复: 你好");
+
+        // 智能体出错时返回 isError 结果，客户端将其转换为错误
+        assert!(matches!(
+            client.call_tool(CHAT_TOOL, json!({"message": "fail"})).await,
+            Err(Error::Other(message)) if message.contains("处理失败")
+        ));
+
+        // 未知工具和缺少参数是协议错误
+        assert!(matches!(
+            client.call_tool("unknown", json!({})).await,
+            Err(Error::Protocol { code: ErrorCode::InvalidParams, .. })
+        ));
+        assert!(matches!(
+            client.call_tool(CHAT_TOOL, json!({})).await,
+            Err(Error::Protocol { code: ErrorCode::InvalidParams, .. })
+        ));
+
+        let _ = client.notify("exit", None).await;
+        let _ = tokio::time::timeout(std::time::Duration::from_secs(1), server_handle).await;
+    }
+
+    #[tokio::test]
+    async fn test_resolve_tool_with_separator_in_server_name() {
+        let handler = AgentServer::new(EchoAgent::new(), Arc::new(ConnectionPool::new()));
+        handler.tool_routes.lock().await.insert(
+            "my__fs__read_file".to_string(),
+            ("my__fs".to_string(), "read_file".to_string()),
+        );
+
+        // 按分隔符拆分会得到服务器 `my`，映射则找回完整的服务器名
+        let (server_name, tool_name) = handler.resolve_tool("my__fs__read_file").await.unwrap();
+        assert_eq!(server_name, "my__fs");
+        assert_eq!(tool_name, "read_file");
+
+        assert!(matches!(
+            handler.resolve_tool("my__read_file").await,
+            Err(Error::Protocol { code: ErrorCode::InvalidParams, .. })
+        ));
+    }
+}
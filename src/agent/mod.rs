@@ -1,10 +1,14 @@
 pub mod types;
 pub mod adapter;
+pub mod sampling;
+pub mod mcp_server;
 
 // 重新导出常用类型
 pub use types::{
     Agent, AgentConfig, AgentContext, AgentState, AgentMessage, MessageType, 
     ToolCall, ToolCallStatus, ToolInfo, BehaviorConfig, WorkspaceConfig, ToolStrategy,
-    DeepSeekConfig
+    DeepSeekConfig, SamplingConfig, SamplingApproval
 };
 pub use adapter::Adapter;
+pub use sampling::SamplingHandler;
+pub use mcp_server::AgentServer;
@@ -0,0 +1,351 @@
+// ============================================
+// MCP采样支持
+// 允许已连接的MCP服务器通过 `sampling/createMessage` 借用智能体配置的LLM
+// ============================================
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::client::{ClientHandler, WorkspaceClientHandler};
+use crate::connection_pool::ClientHandlerFactory;
+use crate::error::{Error, ErrorCode};
+use crate::types::{CreateMessageRequest, CreateMessageResult, IncludeContext, MessageContent, Role};
+
+use super::types::{DeepSeekConfig, SamplingConfig};
+
+/// 应答某个MCP服务器采样请求的客户端处理器
+pub struct SamplingHandler {
+    /// 发起请求的服务器名称（用于审批）
+    server_name: String,
+    /// 用于生成回复的LLM配置
+    llm: DeepSeekConfig,
+    /// 审批策略与token限制
+    config: SamplingConfig,
+    /// HTTP客户端
+    http_client: reqwest::Client,
+    /// 所有服务器共享的已消耗token数
+    used_tokens: Arc<AtomicU64>,
+    /// roots/list 等请求仍由工作区处理器应答
+    roots: WorkspaceClientHandler,
+}
+
+impl SamplingHandler {
+    /// 创建新的采样处理器
+    pub fn new(
+        server_name: &str,
+        llm: DeepSeekConfig,
+        config: SamplingConfig,
+        used_tokens: Arc<AtomicU64>,
+    ) -> Self {
+        Self {
+            server_name: server_name.to_string(),
+            llm,
+            config,
+            http_client: reqwest::Client::new(),
+            used_tokens,
+            roots: WorkspaceClientHandler::default(),
+        }
+    }
+
+    /// 创建连接池使用的处理器工厂，所有服务器共享同一个token额度
+    pub fn factory(llm: DeepSeekConfig, config: SamplingConfig) -> ClientHandlerFactory {
+        let used_tokens = Arc::new(AtomicU64::new(0));
+        Arc::new(move |server_name: &str| {
+            Arc::new(SamplingHandler::new(
+                server_name,
+                llm.clone(),
+                config.clone(),
+                used_tokens.clone(),
+            )) as Arc<dyn ClientHandler>
+        })
+    }
+
+    /// 计算本次请求可用的最大token数
+    fn token_limit(&self, requested: u32) -> Result<u32, Error> {
+        let mut limit = requested.min(self.config.max_tokens_per_request);
+        if let Some(total) = self.config.max_total_tokens {
+            let remaining = total.saturating_sub(self.used_tokens.load(Ordering::Relaxed));
+            if remaining == 0 {
+                return Err(Error::protocol(
+                    ErrorCode::RequestFailed,
+                    "采样token额度已用完",
+                ));
+            }
+            limit = limit.min(remaining.min(u32::MAX as u64) as u32);
+        }
+        Ok(limit)
+    }
+
+    /// 将采样请求转换为聊天补全请求的消息列表
+    fn chat_messages(request: &CreateMessageRequest) -> Result<Vec<serde_json::Value>, Error> {
+        let mut messages = Vec::new();
+        if let Some(system_prompt) = &request.system_prompt {
+            messages.push(json!({"role": "system", "content": system_prompt}));
+        }
+        for message in &request.messages {
+            match &message.content {
+                MessageContent::Text { text, .. } => {
+                    messages.push(json!({"role": message.role, "content": text}));
+                }
+                _ => {
+                    return Err(Error::protocol(
+                        ErrorCode::InvalidParams,
+                        "采样请求仅支持文本内容",
+                    ));
+                }
+            }
+        }
+        Ok(messages)
+    }
+
+    /// 判断输出是否因命中停止序列而结束：接口返回了匹配的序列（如 vLLM 的 `stop_reason`），或文本以某个序列结尾
+    fn ended_on_stop_sequence(
+        choice: &serde_json::Value,
+        text: &str,
+        stop_sequences: &[String],
+    ) -> bool {
+        let matched = choice.get("stop_reason").and_then(|r| r.as_str());
+        stop_sequences
+            .iter()
+            .filter(|sequence| !sequence.is_empty())
+            .any(|sequence| matched == Some(sequence.as_str()) || text.ends_with(sequence.as_str()))
+    }
+}
+
+#[async_trait]
+impl ClientHandler for SamplingHandler {
+    async fn list_roots(&self) -> Result<serde_json::Value, Error> {
+        self.roots.list_roots().await
+    }
+
+    async fn create_message(&self, params: serde_json::Value) -> Result<serde_json::Value, Error> {
+        if !self.config.approval.allows(&self.server_name) {
+            warn!("已拒绝服务器 {} 的采样请求", self.server_name);
+            return Err(Error::protocol(
+                ErrorCode::RequestFailed,
+                format!("服务器 {} 的采样请求未获批准", self.server_name),
+            ));
+        }
+
+        let request: CreateMessageRequest = serde_json::from_value(params)
+            .map_err(|e| Error::protocol(ErrorCode::InvalidParams, e.to_string()))?;
+        let max_tokens = self.token_limit(request.max_tokens)?;
+        if request.model_preferences.is_some() {
+            info!("忽略服务器 {} 的 modelPreferences，使用配置的模型 {}", self.server_name, self.llm.model);
+        }
+        if let Some(context @ (IncludeContext::ThisServer | IncludeContext::AllServers)) =
+            request.include_context
+        {
+            info!("忽略服务器 {} 的 includeContext={:?}，不附加MCP上下文", self.server_name, context);
+        }
+
+        let mut request_body = json!({
+            "model": self.llm.model,
+            "messages": Self::chat_messages(&request)?,
+            "max_tokens": max_tokens,
+            "temperature": request.temperature.unwrap_or(self.llm.temperature),
+            "stream": false
+        });
+        if let Some(stop) = &request.stop_sequences {
+            request_body["stop"] = json!(stop);
+        }
+
+        info!("处理服务器 {} 的采样请求 (max_tokens={})", self.server_name, max_tokens);
+        let response = self.http_client
+            .post(format!("{}/v1/chat/completions", self.llm.base_url))
+            .header("Authorization", format!("Bearer {}", self.llm.api_key))
+            .header("Content-Type", "application/json")
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|e| Error::Other(format!("HTTP请求失败: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(Error::Other(format!("API请求失败: {} - {}", status, error_text)));
+        }
+
+        let response_json: serde_json::Value = response.json().await
+            .map_err(|e| Error::Other(format!("响应解析失败: {}", e)))?;
+
+        let choice = response_json
+            .get("choices")
+            .and_then(|c| c.as_array())
+            .and_then(|c| c.first())
+            .ok_or_else(|| Error::Other("API响应格式不正确".to_string()))?;
+        let text = choice
+            .pointer("/message/content")
+            .and_then(|c| c.as_str())
+            .ok_or_else(|| Error::Other("API响应格式不正确".to_string()))?;
+
+        // 按实际用量计费，接口未返回用量时按上限计
+        let used = response_json
+            .pointer("/usage/total_tokens")
+            .and_then(|t| t.as_u64())
+            .unwrap_or(max_tokens as u64);
+        self.used_tokens.fetch_add(used, Ordering::Relaxed);
+
+        let stop_reason = match choice.get("finish_reason").and_then(|r| r.as_str()) {
+            Some("length") => "maxTokens",
+            Some("stop")
+                if request
+                    .stop_sequences
+                    .as_deref()
+                    .is_some_and(|sequences| Self::ended_on_stop_sequence(choice, text, sequences)) =>
+            {
+                "stopSequence"
+            }
+            _ => "endTurn",
+        };
+
+        let result = CreateMessageResult {
+            role: Role::Assistant,
+            content: MessageContent::text(text),
+            model: response_json
+                .get("model")
+                .and_then(|m| m.as_str())
+                .unwrap_or(&self.llm.model)
+                .to_string(),
+            stop_reason: Some(stop_reason.to_string()),
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::types::SamplingApproval;
+    use warp::Filter;
+
+    fn llm(base_url: String) -> DeepSeekConfig {
+        DeepSeekConfig {
+            base_url,
+            api_key: "test-key".to_string(),
+            model: "deepseek-chat".to_string(),
+            max_tokens: 2000,
+            temperature: 0.7,
+        }
+    }
+
+    fn sampling_params(max_tokens: u32) -> serde_json::Value {
+        json!({
+            "messages": [{"role": "user", "content": {"type": "text", "text": "你好"}}],
+            "systemPrompt": "简短回答",
+            "maxTokens": max_tokens
+        })
+    }
+
+    #[tokio::test]
+    async fn test_denied_server() {
+        let config = SamplingConfig {
+            approval: SamplingApproval::AllowServers(vec!["trusted".to_string()]),
+            ..SamplingConfig::default()
+        };
+        let handler = SamplingHandler::new(
+            "untrusted",
+            llm("http://127.0.0.1:9".to_string()),
+            config,
+            Arc::new(AtomicU64::new(0)),
+        );
+        let result = handler.create_message(sampling_params(100)).await;
+        assert!(matches!(
+            result,
+            Err(Error::Protocol { code: ErrorCode::RequestFailed, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sampling_through_llm_with_token_limits() {
+        // 模拟DeepSeek接口，回显收到的max_tokens
+        let route = warp::post()
+            .and(warp::path!("v1" / "chat" / "completions"))
+            .and(warp::body::json())
+            .map(|body: serde_json::Value| {
+                assert_eq!(body["messages"][0]["role"], "system");
+                warp::reply::json(&json!({
+                    "model": "deepseek-chat",
+                    "choices": [{
+                        "message": {"role": "assistant", "content": body["max_tokens"].to_string()},
+                        "finish_reason": "stop"
+                    }],
+                    "usage": {"total_tokens": 150}
+                }))
+            });
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let config = SamplingConfig {
+            approval: SamplingApproval::AutoApprove,
+            max_tokens_per_request: 100,
+            max_total_tokens: Some(200),
+        };
+        let handler = SamplingHandler::new(
+            "server",
+            llm(format!("http://{}", addr)),
+            config,
+            Arc::new(AtomicU64::new(0)),
+        );
+
+        // 请求的token数被截断到单次上限
+        let result = handler.create_message(sampling_params(500)).await.unwrap();
+        let result: CreateMessageResult = serde_json::from_value(result).unwrap();
+        assert!(matches!(result.content, MessageContent::Text { ref text, .. } if text == "100"));
+        assert_eq!(result.stop_reason.as_deref(), Some("endTurn"));
+
+        // 剩余额度为50
+        let result = handler.create_message(sampling_params(500)).await.unwrap();
+        assert_eq!(result["content"]["text"], "50");
+
+        // 额度用完后拒绝
+        assert!(handler.create_message(sampling_params(500)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stop_reason_follows_matched_stop_sequence() {
+        // 模拟接口：用户消息为“命中”时报告匹配到的停止序列
+        let route = warp::post()
+            .and(warp::path!("v1" / "chat" / "completions"))
+            .and(warp::body::json())
+            .map(|body: serde_json::Value| {
+                assert_eq!(body["stop"], json!(["END"]));
+                let matched = (body["messages"][1]["content"] == "命中").then_some("END");
+                warp::reply::json(&json!({
+                    "choices": [{
+                        "message": {"role": "assistant", "content": "好的"},
+                        "finish_reason": "stop",
+                        "stop_reason": matched
+                    }]
+                }))
+            });
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let config = SamplingConfig {
+            approval: SamplingApproval::AutoApprove,
+            ..SamplingConfig::default()
+        };
+        let handler = SamplingHandler::new(
+            "server",
+            llm(format!("http://{}", addr)),
+            config,
+            Arc::new(AtomicU64::new(0)),
+        );
+
+        for (text, expected) in [("命中", "stopSequence"), ("你好", "endTurn")] {
+            let params = json!({
+                "messages": [{"role": "user", "content": {"type": "text", "text": text}}],
+                "systemPrompt": "简短回答",
+                "includeContext": "thisServer",
+                "stopSequences": ["END"],
+                "maxTokens": 100
+            });
+            let result = handler.create_message(params).await.unwrap();
+            assert_eq!(result["stopReason"], expected);
+        }
+    }
+}
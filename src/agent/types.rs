@@ -28,6 +28,53 @@ pub struct AgentConfig {
     pub behavior: BehaviorConfig,
     /// 工作空间配置
     pub workspace: WorkspaceConfig,
+    /// MCP采样配置（允许服务器借用智能体的LLM）
+    #[serde(default)]
+    pub sampling: SamplingConfig,
+}
+
+/// MCP采样配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplingConfig {
+    /// 审批策略
+    pub approval: SamplingApproval,
+    /// 单次采样请求允许的最大token数，超出的请求会被截断到此值
+    pub max_tokens_per_request: u32,
+    /// 所有采样请求累计可消耗的token上限，None表示不限制
+    #[serde(default)]
+    pub max_total_tokens: Option<u64>,
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
+            approval: SamplingApproval::Deny,
+            max_tokens_per_request: 1000,
+            max_total_tokens: Some(20000),
+        }
+    }
+}
+
+/// 采样请求审批策略
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SamplingApproval {
+    /// 拒绝所有采样请求
+    Deny,
+    /// 自动批准所有服务器的采样请求
+    AutoApprove,
+    /// 仅批准列表中的服务器
+    AllowServers(Vec<String>),
+}
+
+impl SamplingApproval {
+    /// 判断是否允许指定服务器发起采样
+    pub fn allows(&self, server_name: &str) -> bool {
+        match self {
+            SamplingApproval::Deny => false,
+            SamplingApproval::AutoApprove => true,
+            SamplingApproval::AllowServers(servers) => servers.iter().any(|s| s == server_name),
+        }
+    }
 }
 
 /// 智能体行为配置
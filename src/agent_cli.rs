@@ -1,16 +1,24 @@
+use std::collections::HashMap;
 use std::env;
 use std::sync::Arc;
 use clap::{Parser, Subcommand};
 use anyhow::Result;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::{Context, Editor, Helper, Highlighter, Hinter, Validator};
 use tracing::{info, error, warn, debug};
-use tokio;
 use std::time::Duration;
+use tokio::sync::broadcast;
 
 use alou::agent::{
-    Agent, Adapter, AgentConfig, DeepSeekConfig, BehaviorConfig,
+    Agent, Adapter, AgentConfig, AgentServer, DeepSeekConfig, BehaviorConfig, SamplingConfig,
     WorkspaceConfig, ToolStrategy
 };
-use alou::connection_pool::ConnectionPool;
+use alou::connection_pool::{ConnectionPool, ToolProgress};
+use alou::prompt_registry::{DiscoveredMcpPrompt, PromptRegistry};
+use alou::server::Server;
+use alou::transport::stdio::StdioTransport;
 
 /// 智能体CLI工具
 #[derive(Parser)]
@@ -46,6 +54,12 @@ enum Commands {
         #[arg(short, long, default_value = "agent_config.json")]
         config: String,
     },
+    /// 作为MCP服务器运行，通过标准输入输出提供 `chat` 工具和已配置服务器的工具
+    ServeMcp {
+        /// 配置文件路径
+        #[arg(short, long, default_value = "agent_config.json")]
+        config: String,
+    },
     /// 初始化配置文件
     Init {
         /// 输出配置文件路径
@@ -81,6 +95,7 @@ fn get_default_config() -> AgentConfig {
             smart_detection: true,
             exclude_patterns: vec!["target".to_string(), "node_modules".to_string()],
         },
+        sampling: SamplingConfig::default(),
     }
 }
 
@@ -121,22 +136,167 @@ fn save_config(config: &AgentConfig, config_path: &str) -> Result<()> {
     Ok(())
 }
 
-/// 简单的加载动画
-async fn show_loading_animation(message: &str) {
+/// 进度条宽度（字符数）
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+/// 渲染工具进度：服务器给出总量时显示进度条，否则显示已完成的数量
+fn render_tool_progress(update: &ToolProgress, spinner: &str) -> String {
+    let progress = &update.progress;
+    let message = progress.message.as_deref().unwrap_or_default();
+    match progress.total.filter(|total| *total > 0.0) {
+        Some(total) => {
+            let ratio = (progress.progress / total).clamp(0.0, 1.0);
+            let filled = (ratio * PROGRESS_BAR_WIDTH as f64).round() as usize;
+            format!(
+                "🔧 {} [{}{}] {:>3.0}% {}",
+                update.tool_name,
+                "█".repeat(filled),
+                "░".repeat(PROGRESS_BAR_WIDTH - filled),
+                ratio * 100.0,
+                message
+            )
+        }
+        None => format!("{} 🔧 {} {} {}", spinner, update.tool_name, progress.progress, message),
+    }
+}
+
+/// 加载动画；工具报告进度时改为显示进度条
+async fn show_progress(
+    message: &str,
+    mut progress_rx: broadcast::Receiver<ToolProgress>,
+    mut out: impl std::io::Write,
+) {
     let spinner_chars = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
     let mut index = 0;
-    
-    print!("\r{} {}", spinner_chars[index], message);
-    std::io::Write::flush(&mut std::io::stdout()).unwrap();
+    let mut latest: Option<ToolProgress> = None;
+    let mut closed = false;
+    let mut ticker = tokio::time::interval(Duration::from_millis(100));
     
     loop {
-        tokio::time::sleep(Duration::from_millis(100)).await;
-        index = (index + 1) % spinner_chars.len();
-        print!("\r{} {}", spinner_chars[index], message);
-        std::io::Write::flush(&mut std::io::stdout()).unwrap();
+        tokio::select! {
+            _ = ticker.tick() => index = (index + 1) % spinner_chars.len(),
+            update = progress_rx.recv(), if !closed => match update {
+                Ok(update) => latest = Some(update),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => closed = true,
+            },
+        }
+        
+        let line = match &latest {
+            Some(update) => render_tool_progress(update, spinner_chars[index]),
+            None => format!("{} {}", spinner_chars[index], message),
+        };
+        // 先清除整行，进度条可能比之前的内容短
+        let _ = write!(out, "\r\x1b[2K{}", line);
+        let _ = out.flush();
+    }
+}
+
+/// 交互输入的补全器：以 `/` 开头时补全服务器提示名、参数名，
+/// 参数值通过服务器的 `completion/complete` 获取
+#[derive(Helper, Highlighter, Hinter, Validator)]
+struct PromptHelper {
+    prompts: HashMap<String, DiscoveredMcpPrompt>,
+    runtime: tokio::runtime::Handle,
+}
+
+impl PromptHelper {
+    /// 提示定义中声明的参数名
+    fn argument_names(prompt: &DiscoveredMcpPrompt) -> Vec<String> {
+        prompt
+            .arguments
+            .iter()
+            .flatten()
+            .filter_map(|argument| argument.get("name")?.as_str().map(str::to_string))
+            .collect()
+    }
+}
+
+impl Completer for PromptHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        let Some(command) = line.strip_prefix('/') else {
+            return Ok((pos, Vec::new()));
+        };
+
+        let word_start = line.rfind(char::is_whitespace).map_or(0, |index| index + 1);
+        if word_start == 0 {
+            // 补全提示名
+            let mut names: Vec<String> = self
+                .prompts
+                .keys()
+                .filter(|name| name.starts_with(command))
+                .cloned()
+                .collect();
+            names.sort();
+            return Ok((1, names));
+        }
+
+        let mut words = line.split_whitespace();
+        let prompt_name = words.next().unwrap_or_default().trim_start_matches('/');
+        let Some(prompt) = self.prompts.get(prompt_name) else {
+            return Ok((pos, Vec::new()));
+        };
+
+        let word = &line[word_start..];
+        match word.split_once('=') {
+            Some((argument, value)) => {
+                // 已填写的其他参数作为补全上下文
+                let context = parse_prompt_arguments(&line[..word_start])
+                    .into_iter()
+                    .filter_map(|(key, value)| Some((key, value.as_str()?.to_string())))
+                    .collect();
+                let values = self
+                    .runtime
+                    .block_on(prompt.complete(argument, value, context))
+                    .unwrap_or_else(|e| {
+                        debug!("补全参数 {} 失败: {}", argument, e);
+                        Vec::new()
+                    });
+                Ok((word_start + argument.len() + 1, values))
+            }
+            None => {
+                let names = Self::argument_names(prompt)
+                    .into_iter()
+                    .filter(|name| name.starts_with(word))
+                    .map(|name| format!("{}=", name))
+                    .collect();
+                Ok((word_start, names))
+            }
+        }
     }
 }
 
+/// 解析 `key=value` 形式的提示参数
+fn parse_prompt_arguments(input: &str) -> HashMap<String, serde_json::Value> {
+    input
+        .split_whitespace()
+        .filter_map(|word| word.split_once('='))
+        .map(|(key, value)| (key.to_string(), serde_json::Value::String(value.to_string())))
+        .collect()
+}
+
+/// 调用服务器提示，返回拼接后的文本内容
+async fn invoke_prompt(registry: &PromptRegistry, input: &str) -> Result<String> {
+    let (name, arguments) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    let prompt = registry
+        .get_prompt(name)
+        .ok_or_else(|| anyhow::anyhow!("未找到提示: {}", name))?;
+    let result = prompt.invoke(parse_prompt_arguments(arguments)).await?;
+    let text: Vec<&str> = result
+        .messages
+        .iter()
+        .filter_map(|message| message.content.as_text())
+        .collect();
+    Ok(text.join("\n"))
+}
 
 /// 初始化MCP连接池
 async fn init_connection_pool() -> Result<ConnectionPool> {
@@ -161,32 +321,79 @@ async fn start_chat(config_path: &str) -> Result<()> {
     // 初始化智能体
     agent.initialize().await?;
     
+    // 发现服务器提供的提示，供 `/提示名` 调用和补全
+    let mut prompt_registry = PromptRegistry::new();
+    let prompt_count = connection_pool.discover_all_prompts(&mut prompt_registry).await;
+    debug!("发现 {} 个服务器提示", prompt_count);
+    
+    let mut editor: Editor<PromptHelper, DefaultHistory> = Editor::new()?;
+    editor.set_helper(Some(PromptHelper {
+        prompts: prompt_registry
+            .get_all_prompts()
+            .into_iter()
+            .map(|prompt| (prompt.name.clone(), prompt.clone()))
+            .collect(),
+        runtime: tokio::runtime::Handle::current(),
+    }));
+    
     // 显示欢迎界面
     println!("\n✨ Alou智能助手已就绪！");
     println!("💡 输入 'exit' 或 'quit' 退出程序");
+    if prompt_count > 0 {
+        println!("💡 输入 '/提示名 参数=值' 调用服务器提示，按 Tab 补全");
+    }
     println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
     
     // 简单的交互循环
     loop {
-        print!("👤 我: ");
-        std::io::Write::flush(&mut std::io::stdout())?;
+        // 行编辑器会阻塞线程，补全时还需在运行时上等待服务器响应
+        let (returned_editor, line) = tokio::task::spawn_blocking(move || {
+            let line = editor.readline("👤 我: ");
+            (editor, line)
+        })
+        .await?;
+        editor = returned_editor;
         
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input)?;
+        let input = match line {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => {
+                println!("\n👋 感谢使用Alou智能助手，再见！");
+                break;
+            }
+            Err(e) => return Err(e.into()),
+        };
         let input = input.trim();
         
         if input.is_empty() {
             continue;
         }
+        let _ = editor.add_history_entry(input);
         
         if input == "exit" || input == "quit" {
             println!("\n👋 感谢使用Alou智能助手，再见！");
             break;
         }
         
-        // 显示加载动画
-        let loading_handle = tokio::spawn(async {
-            show_loading_animation("🤔 正在思考，请稍候...").await;
+        // 以 `/` 开头时先调用服务器提示，用其内容作为输入
+        let prompt_text;
+        let input = match input.strip_prefix('/') {
+            Some(command) => match invoke_prompt(&prompt_registry, command).await {
+                Ok(text) => {
+                    prompt_text = text;
+                    prompt_text.as_str()
+                }
+                Err(e) => {
+                    error!("调用提示失败: {}", e);
+                    continue;
+                }
+            },
+            None => input,
+        };
+        
+        // 显示加载动画，工具报告进度时显示进度条
+        let progress_rx = connection_pool.subscribe_tool_progress();
+        let loading_handle = tokio::spawn(async move {
+            show_progress("🤔 正在思考，请稍候...", progress_rx, std::io::stdout()).await;
         });
         
         // 处理API调用
@@ -196,8 +403,7 @@ async fn start_chat(config_path: &str) -> Result<()> {
         loading_handle.abort();
         
         // 清除加载动画行
-        print!("\r{}", " ".repeat(50));
-        print!("\r");
+        print!("\r\x1b[2K");
         std::io::Write::flush(&mut std::io::stdout()).unwrap();
         
         match result {
@@ -233,9 +439,10 @@ async fn test_agent(config_path: &str, message: &str) -> Result<()> {
     
     info!("测试消息: {}", message);
     
-    // 显示加载动画
-    let loading_handle = tokio::spawn(async {
-        show_loading_animation("🤔 正在思考，请稍候...").await;
+    // 显示加载动画，工具报告进度时显示进度条
+    let progress_rx = connection_pool.subscribe_tool_progress();
+    let loading_handle = tokio::spawn(async move {
+        show_progress("🤔 正在思考，请稍候...", progress_rx, std::io::stdout()).await;
     });
     
     // 处理API调用
@@ -245,8 +452,7 @@ async fn test_agent(config_path: &str, message: &str) -> Result<()> {
     loading_handle.abort();
     
     // 清除加载动画行
-    print!("\r{}", " ".repeat(50));
-    print!("\r");
+    print!("\r\x1b[2K");
     std::io::Write::flush(&mut std::io::stdout()).unwrap();
     
     match result {
@@ -262,6 +468,35 @@ async fn test_agent(config_path: &str, message: &str) -> Result<()> {
     Ok(())
 }
 
+/// 以MCP服务器形式运行智能体，供IDE或其他智能体调用
+async fn serve_mcp(config_path: &str) -> Result<()> {
+    let config = load_config(config_path)?;
+    
+    // 初始化连接池
+    let connection_pool = Arc::new(init_connection_pool().await?);
+    
+    // 创建并初始化智能体
+    let mut agent = Adapter::with_connection_pool(config, connection_pool.clone()).await?;
+    agent.initialize().await?;
+    
+    // 标准输出只用于MCP消息
+    let transport = StdioTransport::with_streams(tokio::io::stdin(), tokio::io::stdout())?;
+    let handler = AgentServer::new(agent, connection_pool.clone());
+    info!("Alou MCP服务器已启动");
+    
+    // 客户端断开时输入流结束，服务器随之停止
+    if let Err(e) = Server::new(Arc::new(transport), Arc::new(handler)).start().await {
+        debug!("MCP服务器已停止: {}", e);
+    }
+    
+    // 优雅关闭连接
+    if let Err(e) = connection_pool.close_all_connections().await {
+        debug!("关闭连接时出现错误: {}", e);
+    }
+    
+    Ok(())
+}
+
 /// 初始化配置文件
 fn init_config(output_path: &str) -> Result<()> {
     let config = get_default_config();
@@ -283,7 +518,19 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
     
     // 根据模式设置日志级别
-    if cli.clean {
+    if matches!(cli.command, Commands::ServeMcp { .. }) {
+        // MCP服务器模式：标准输出用于协议消息，日志写到标准错误
+        tracing_subscriber::fmt()
+            .with_max_level(if cli.quiet || cli.clean {
+                tracing::Level::ERROR
+            } else {
+                tracing::Level::WARN
+            })
+            .with_target(false)
+            .with_ansi(false)
+            .with_writer(std::io::stderr)
+            .init();
+    } else if cli.clean {
         // 清洁模式：完全隐藏所有日志，只显示用户界面
         tracing_subscriber::fmt()
             .with_max_level(tracing::Level::ERROR)
@@ -317,6 +564,9 @@ async fn main() -> Result<()> {
         Commands::Test { message, config } => {
             test_agent(&config, &message).await?;
         }
+        Commands::ServeMcp { config } => {
+            serve_mcp(&config).await?;
+        }
         Commands::Init { output } => {
             init_config(&output)?;
         }
@@ -324,3 +574,119 @@ async fn main() -> Result<()> {
     
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alou::agent::{ToolCall, ToolCallStatus};
+    use alou::connection_pool::McpServerConfig;
+    use alou::error::Error;
+    use alou::server::{RequestContext, Server, ServerHandler};
+    use alou::transport::socket::TcpTransport;
+    use alou::types::{ClientCapabilities, Implementation, ServerCapabilities, ToolsCapability};
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// 执行时报告一半进度的转账工具
+    struct TransferServer;
+
+    #[async_trait::async_trait]
+    impl ServerHandler for TransferServer {
+        async fn initialize(
+            &self,
+            _implementation: Implementation,
+            _capabilities: ClientCapabilities,
+        ) -> Result<ServerCapabilities, Error> {
+            Ok(ServerCapabilities {
+                tools: Some(ToolsCapability::default()),
+                ..Default::default()
+            })
+        }
+
+        async fn shutdown(&self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn handle_method(
+            &self,
+            method: &str,
+            _params: Option<serde_json::Value>,
+        ) -> Result<serde_json::Value, Error> {
+            match method {
+                "tools/list" => Ok(json!({"tools": [{"name": "transfer", "inputSchema": {"type": "object"}}]})),
+                _ => Ok(json!({"content": [{"type": "text", "text": "已转账"}]})),
+            }
+        }
+
+        async fn handle_method_with_context(
+            &self,
+            method: &str,
+            params: Option<serde_json::Value>,
+            context: RequestContext,
+        ) -> Result<serde_json::Value, Error> {
+            if let Some(progress) = context.progress {
+                progress.report(1.0, Some(2.0), Some("签名中".to_string())).await?;
+                // 留出时间让进度条渲染
+                tokio::time::sleep(Duration::from_millis(300)).await;
+            }
+            self.handle_method(method, params).await
+        }
+    }
+
+    /// 记录写入内容的输出
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_progress_shows_bar() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let transport = TcpTransport::from_stream(stream).unwrap();
+            Server::new(Arc::new(transport), Arc::new(TransferServer)).start().await
+        });
+
+        let pool = Arc::new(ConnectionPool::new());
+        let server_config = McpServerConfig {
+            command: String::new(),
+            args: Vec::new(),
+            directory: None,
+            env: None,
+            url: Some(format!("tcp://{}", addr)),
+            log_level: None,
+        };
+        pool.register_server("wallet".to_string(), server_config).await;
+        let mut agent = Adapter::with_connection_pool(get_default_config(), pool.clone())
+            .await
+            .unwrap();
+        assert_eq!(agent.discover_tools().await, 1);
+
+        // 智能体的工具调用经过连接池，进度广播给加载动画
+        let output = SharedOutput::default();
+        let progress = tokio::spawn(show_progress("思考中", pool.subscribe_tool_progress(), output.clone()));
+        let call = ToolCall {
+            name: "transfer".to_string(),
+            arguments: HashMap::new(),
+            call_id: "call-1".to_string(),
+            status: ToolCallStatus::Pending,
+        };
+        assert_eq!(agent.execute_tool(&call).await.unwrap(), json!("已转账"));
+        progress.abort();
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let bar = format!("🔧 transfer [{}{}]  50% 签名中", "█".repeat(15), "░".repeat(15));
+        assert!(output.contains(&bar), "进度条未显示: {}", output);
+    }
+}
@@ -48,7 +48,7 @@ async fn main() -> Result<()> {
         println!("{}", "=".repeat(50));
         
         if let Ok(connection) = pool.get_connection(server_name).await {
-            let client = connection.lock().await;
+            let client = &connection;
             
             // 获取工具列表
             match client.request("tools/list", None).await {
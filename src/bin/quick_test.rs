@@ -27,7 +27,7 @@ async fn main() -> Result<()> {
     }
 
     let client = pool.get_connection("filesystem").await?;
-    let client_guard = client.lock().await;
+    let client_guard = &client;
     
     // 测试创建目录
     println!("创建目录 '444'...");
@@ -9,7 +9,7 @@ use anyhow::Result;
 use tracing::{info, error, warn};
 
 use alou::agent::{
-    Agent, Adapter, AgentConfig, DeepSeekConfig, BehaviorConfig, 
+    Agent, Adapter, AgentConfig, DeepSeekConfig, BehaviorConfig, SamplingConfig, 
     WorkspaceConfig, ToolStrategy
 };
 use alou::connection_pool::ConnectionPool;
@@ -73,6 +73,7 @@ fn get_default_config() -> AgentConfig {
             smart_detection: true,
             exclude_patterns: vec!["target".to_string(), "node_modules".to_string()],
         },
+        sampling: SamplingConfig::default(),
     }
 }
 
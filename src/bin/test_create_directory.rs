@@ -42,7 +42,7 @@ async fn main() -> Result<()> {
 
     // 测试创建目录
     let client = pool.get_connection("filesystem").await?;
-    let client_guard = client.lock().await;
+    let client_guard = &client;
     
     println!("1. 创建目录 '120':");
     let result = client_guard.call_tool("create_directory", serde_json::json!({
@@ -50,7 +50,7 @@ async fn main() -> Result<()> {
     })).await?;
     
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   结果: {}", text);
         }
     }
@@ -61,7 +61,7 @@ async fn main() -> Result<()> {
     })).await?;
     
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   目录内容:\n{}", text);
         }
     }
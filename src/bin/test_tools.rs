@@ -62,13 +62,13 @@ async fn test_filesystem_tools(pool: &ConnectionPool) -> Result<()> {
     println!("{}", "=".repeat(50));
     
     let client = pool.get_connection("filesystem").await?;
-    let client_guard = client.lock().await;
+    let client_guard = &client;
     
     // 1. 列出允许的目录
     println!("1. 列出允许的目录:");
     let result = client_guard.call_tool("list_allowed_directories", serde_json::json!({})).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   {}", text);
         }
     }
@@ -79,7 +79,7 @@ async fn test_filesystem_tools(pool: &ConnectionPool) -> Result<()> {
         "path": "."
     })).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   {}", text);
         }
     }
@@ -91,7 +91,7 @@ async fn test_filesystem_tools(pool: &ConnectionPool) -> Result<()> {
         "content": "这是一个通过MCP客户端创建的测试文件！\n创建时间: 2025-09-15\n"
     })).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   {}", text);
         }
     }
@@ -102,7 +102,7 @@ async fn test_filesystem_tools(pool: &ConnectionPool) -> Result<()> {
         "path": "test_mcp_file.txt"
     })).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   文件内容: {}", text);
         }
     }
@@ -113,7 +113,7 @@ async fn test_filesystem_tools(pool: &ConnectionPool) -> Result<()> {
         "path": "test_mcp_file.txt"
     })).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   {}", text);
         }
     }
@@ -127,7 +127,7 @@ async fn test_memory_tools(pool: &ConnectionPool) -> Result<()> {
     println!("{}", "=".repeat(50));
     
     let client = pool.get_connection("memory").await?;
-    let client_guard = client.lock().await;
+    let client_guard = &client;
     
     // 1. 创建实体
     println!("1. 创建知识图谱实体:");
@@ -154,7 +154,7 @@ async fn test_memory_tools(pool: &ConnectionPool) -> Result<()> {
         ]
     })).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   {}", text);
         }
     }
@@ -171,7 +171,7 @@ async fn test_memory_tools(pool: &ConnectionPool) -> Result<()> {
         ]
     })).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   {}", text);
         }
     }
@@ -182,7 +182,7 @@ async fn test_memory_tools(pool: &ConnectionPool) -> Result<()> {
         "query": "MCP"
     })).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   {}", text);
         }
     }
@@ -191,7 +191,7 @@ async fn test_memory_tools(pool: &ConnectionPool) -> Result<()> {
     println!("\n4. 读取整个知识图谱:");
     let result = client_guard.call_tool("read_graph", serde_json::json!({})).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   {}", text);
         }
     }
@@ -205,13 +205,13 @@ async fn test_payment_tools(pool: &ConnectionPool) -> Result<()> {
     println!("{}", "=".repeat(50));
     
     let client = pool.get_connection("payment-npm").await?;
-    let client_guard = client.lock().await;
+    let client_guard = &client;
     
     // 1. 获取网络信息
     println!("1. 获取网络信息:");
     let result = client_guard.call_tool("get_network_info", serde_json::json!({})).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   {}", text);
         }
     }
@@ -220,7 +220,7 @@ async fn test_payment_tools(pool: &ConnectionPool) -> Result<()> {
     println!("\n2. 获取支持的代币列表:");
     let result = client_guard.call_tool("get_supported_tokens", serde_json::json!({})).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   {}", text);
         }
     }
@@ -231,7 +231,7 @@ async fn test_payment_tools(pool: &ConnectionPool) -> Result<()> {
         "label": "test_wallet"
     })).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   {}", text);
         }
     }
@@ -240,7 +240,7 @@ async fn test_payment_tools(pool: &ConnectionPool) -> Result<()> {
     println!("\n4. 列出所有钱包:");
     let result = client_guard.call_tool("list_wallets", serde_json::json!({})).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   {}", text);
         }
     }
@@ -249,7 +249,7 @@ async fn test_payment_tools(pool: &ConnectionPool) -> Result<()> {
     println!("\n5. 估算Gas费用:");
     let result = client_guard.call_tool("estimate_gas_fees", serde_json::json!({})).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   {}", text);
         }
     }
@@ -261,7 +261,7 @@ async fn test_payment_tools(pool: &ConnectionPool) -> Result<()> {
         "address": test_address
     })).await?;
     for content in &result.content {
-        if let alou::types::MessageContent::Text { text } = content {
+        if let alou::types::MessageContent::Text { text, .. } = content {
             println!("   地址 {} 验证结果: {}", test_address, text);
         }
     }
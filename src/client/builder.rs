@@ -1,7 +1,12 @@
-use crate::client::Client;
+use crate::client::{Client, ClientHandler};
 use crate::error::Error;
+use crate::transport::http::HttpClientTransport;
+use crate::transport::socket::TcpTransport;
 use crate::transport::stdio::StdioTransport;
-use crate::types::{ClientCapabilities, Implementation};
+use crate::transport::websocket::WebSocketTransport;
+use crate::transport::Transport;
+use crate::types::{ClientCapabilities, Implementation, LoggingMessageNotification};
+use futures::StreamExt;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::process::Stdio;
@@ -9,7 +14,8 @@ use std::sync::Arc;
 use tokio::process::Command;
 
 /// A builder for creating and initializing an MCP `Client` with a subprocess using stdio transport.
-/// This can be used to spawn a local MCP-compatible process and connect automatically.
+/// This can be used to spawn a local MCP-compatible process and connect automatically,
+/// or to attach to an already running server with [`ClientBuilder::connect`].
 pub struct ClientBuilder {
     /// The command/binary to invoke, e.g. "uvx".
     command: String,
@@ -23,8 +29,17 @@ pub struct ClientBuilder {
     capabilities: Option<ClientCapabilities>,
     /// Environment variables for the subprocess.
     env: HashMap<String, String>,
+    /// Optional handler answering requests sent by the server.
+    handler: Option<Arc<dyn ClientHandler>>,
+    /// Protocol version to request; defaults to the latest supported one.
+    protocol_version: Option<String>,
+    /// Optional callback for every log message the server sends.
+    log_handler: Option<LogHandler>,
 }
 
+/// Receives the `notifications/message` log messages a server sends.
+type LogHandler = Box<dyn Fn(LoggingMessageNotification) + Send + Sync>;
+
 impl ClientBuilder {
     pub fn new(command: &str) -> Self {
         tracing::debug!(%command, "Creating new ClientBuilder");
@@ -35,6 +50,9 @@ impl ClientBuilder {
             implementation: None,
             capabilities: None,
             env: HashMap::new(),
+            handler: None,
+            protocol_version: None,
+            log_handler: None,
         }
     }
 
@@ -68,6 +86,7 @@ impl ClientBuilder {
         self.implementation = Some(Implementation {
             name: name.to_string(),
             version: version.to_string(),
+            title: None,
         });
         self
     }
@@ -85,6 +104,34 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets the handler that answers requests sent by the server, such as
+    /// `roots/list` or `sampling/createMessage`.
+    pub fn handler(mut self, handler: Arc<dyn ClientHandler>) -> Self {
+        tracing::trace!("Setting client handler for ClientBuilder");
+        self.handler = Some(handler);
+        self
+    }
+
+    /// Requests a specific protocol version instead of the latest supported one,
+    /// e.g. for servers that only speak `2024-11-05`.
+    pub fn protocol_version(mut self, version: &str) -> Self {
+        tracing::trace!(%version, "Setting protocol version for ClientBuilder");
+        self.protocol_version = Some(version.to_string());
+        self
+    }
+
+    /// Calls `handler` with every log message the server sends. Unlike subscribing to
+    /// [`Client::log_messages`] on the returned client, this also sees messages sent
+    /// while the server handles `initialize`.
+    pub fn on_log_message<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(LoggingMessageNotification) + Send + Sync + 'static,
+    {
+        tracing::trace!("Setting log message handler for ClientBuilder");
+        self.log_handler = Some(Box::new(handler));
+        self
+    }
+
     /// Spawns the subprocess using the stored command, arguments, etc.,
     /// creates a `StdioTransport` from the subprocess's stdin/stdout,
     /// then returns an initialized `Client`.
@@ -135,12 +182,83 @@ impl ClientBuilder {
 
         tracing::debug!("Creating StdioTransport");
         let transport = StdioTransport::with_streams(child_stdout, child_stdin)?;
-        let client = Client::new(Arc::new(transport), Some(child));
+        self.initialize_client(Arc::new(transport), Some(child)).await
+    }
+
+    /// Connects to an already running MCP server identified by `url`, then returns an
+    /// initialized `Client`. Supported schemes:
+    ///
+    /// - `tcp://host:port` - newline-delimited JSON over TCP
+    /// - `unix:///path/to/socket` - newline-delimited JSON over a Unix domain socket
+    /// - `http://...` / `https://...` - Streamable HTTP
+    /// - `ws://...` / `wss://...` - WebSocket
+    /// - `stdio:` - spawns the builder's command, same as `spawn_and_initialize`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the scheme is unsupported, the connection cannot be
+    /// established, or if initialization fails.
+    pub async fn connect(self, url: &str) -> Result<Client, Error> {
+        tracing::info!(%url, "Connecting to MCP server");
+
+        let transport: Arc<dyn Transport> = if let Some(addr) = url.strip_prefix("tcp://") {
+            Arc::new(TcpTransport::connect(addr.trim_end_matches('/')).await?)
+        } else if let Some(path) = url.strip_prefix("unix://") {
+            Self::connect_unix(path).await?
+        } else if url.starts_with("http://") || url.starts_with("https://") {
+            Arc::new(HttpClientTransport::new(url))
+        } else if url.starts_with("ws://") || url.starts_with("wss://") {
+            Arc::new(WebSocketTransport::connect(url).await?)
+        } else if url.starts_with("stdio:") {
+            return self.spawn_and_initialize().await;
+        } else {
+            return Err(Error::Transport(format!("Unsupported MCP server URL: {}", url)));
+        };
+
+        self.initialize_client(transport, None).await
+    }
+
+    #[cfg(unix)]
+    async fn connect_unix(path: &str) -> Result<Arc<dyn Transport>, Error> {
+        use crate::transport::socket::UnixTransport;
+        Ok(Arc::new(UnixTransport::connect(path).await?))
+    }
+
+    #[cfg(not(unix))]
+    async fn connect_unix(path: &str) -> Result<Arc<dyn Transport>, Error> {
+        Err(Error::Transport(format!(
+            "Unix domain sockets are not supported on this platform: {}",
+            path
+        )))
+    }
+
+    /// Creates the client and runs the initialize handshake using the configured
+    /// implementation and capabilities.
+    async fn initialize_client(
+        self,
+        transport: Arc<dyn Transport>,
+        subprocess: Option<tokio::process::Child>,
+    ) -> Result<Client, Error> {
+        let client = match self.handler {
+            Some(handler) => Client::with_handler(transport, subprocess, handler),
+            None => Client::new(transport, subprocess),
+        };
+
+        // Subscribe before the handshake so no startup log message is missed.
+        if let Some(log_handler) = self.log_handler {
+            let mut messages = client.log_messages();
+            tokio::spawn(async move {
+                while let Some(message) = messages.next().await {
+                    log_handler(message);
+                }
+            });
+        }
 
         let implementation = self.implementation.unwrap_or_else(|| {
             let default_impl = Implementation {
                 name: "mcp-client".to_string(),
                 version: "0.1.2".to_string(),
+                title: None,
             };
             tracing::debug!(?default_impl, "Using default implementation");
             default_impl
@@ -152,9 +270,15 @@ impl ClientBuilder {
         });
 
         tracing::debug!(?implementation, ?capabilities, "Initializing client");
-        client.initialize(implementation, capabilities).await?;
+        let version = self
+            .protocol_version
+            .as_deref()
+            .unwrap_or(crate::LATEST_PROTOCOL_VERSION);
+        client
+            .initialize_with_version(implementation, capabilities, version)
+            .await?;
 
-        tracing::info!("MCP client successfully spawned and initialized");
+        tracing::info!("MCP client successfully connected and initialized");
         Ok(client)
     }
 }
@@ -177,4 +301,62 @@ mod tests {
             );
         });
     }
+
+    #[tokio::test]
+    async fn test_connect_unsupported_scheme() {
+        let result = ClientBuilder::new("").connect("ftp://example.com").await;
+        assert!(matches!(result, Err(Error::Transport(_))));
+    }
+
+    #[tokio::test]
+    async fn test_connect_tcp() {
+        use crate::protocol::{Notification, Response};
+        use crate::transport::Message;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // A stand-in daemon that completes the initialize handshake.
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let transport = TcpTransport::from_stream(stream).unwrap();
+            let mut incoming = transport.receive();
+            while let Some(Ok(message)) = incoming.next().await {
+                if let Message::Request(request) = message {
+                    // Logged while initializing, before `connect` returns the client.
+                    let log = serde_json::json!({"level": "info", "data": "starting up"});
+                    let _ = transport
+                        .send(Message::Notification(Notification::new(
+                            "notifications/message",
+                            Some(log),
+                        )))
+                        .await;
+                    let result = serde_json::json!({
+                        "protocolVersion": crate::LATEST_PROTOCOL_VERSION,
+                        "serverInfo": {"name": "daemon", "version": "1.0.0"},
+                        "capabilities": {}
+                    });
+                    let _ = transport
+                        .send(Message::Response(Response::success(request.id, Some(result))))
+                        .await;
+                }
+            }
+        });
+
+        let (logged, mut logs) = tokio::sync::mpsc::unbounded_channel();
+        let client = ClientBuilder::new("")
+            .implementation("test-client", "0.1.0")
+            .on_log_message(move |message| {
+                let _ = logged.send(message);
+            })
+            .connect(&format!("tcp://{}", addr))
+            .await
+            .unwrap();
+        assert!(client.capabilities().await.is_some());
+        let message = tokio::time::timeout(std::time::Duration::from_secs(5), logs.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.data, "starting up");
+    }
 }
@@ -0,0 +1,167 @@
+//! Handling of requests sent by the server to the client.
+//!
+//! MCP servers may call back into the client, e.g. `ping`, `roots/list` or
+//! `sampling/createMessage`. A [`ClientHandler`] registered with
+//! [`ClientBuilder::handler`](crate::client::ClientBuilder::handler) answers them.
+
+use async_trait::async_trait;
+use std::path::Path;
+
+use crate::{
+    error::{Error, ErrorCode},
+    types::{ListRootsResult, Root},
+    workspace_context::{WorkspaceContext, WorkspaceContextFactory},
+};
+
+/// Trait for answering server→client requests.
+///
+/// Every method has a default: `ping` succeeds, `roots/list` reports the current
+/// directory, and everything else is answered with "method not found".
+#[async_trait]
+pub trait ClientHandler: Send + Sync {
+    /// Routes a request from the server to the matching method below.
+    async fn handle_request(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, Error> {
+        match method {
+            "ping" => {
+                self.ping().await?;
+                Ok(serde_json::json!({}))
+            }
+            "roots/list" => self.list_roots().await,
+            "sampling/createMessage" => {
+                self.create_message(params.unwrap_or(serde_json::Value::Null))
+                    .await
+            }
+            _ => self.handle_method(method, params).await,
+        }
+    }
+
+    /// Handle `ping`
+    async fn ping(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Handle `roots/list`, returning a `{"roots": [...]}` result
+    async fn list_roots(&self) -> Result<serde_json::Value, Error> {
+        Ok(workspace_roots(
+            WorkspaceContextFactory::create_basic().as_ref(),
+        ))
+    }
+
+    /// Handle `sampling/createMessage`
+    async fn create_message(&self, _params: serde_json::Value) -> Result<serde_json::Value, Error> {
+        Err(Error::protocol(
+            ErrorCode::MethodNotFound,
+            "Sampling is not supported by this client",
+        ))
+    }
+
+    /// Handle any other method
+    async fn handle_method(
+        &self,
+        method: &str,
+        _params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, Error> {
+        Err(Error::protocol(
+            ErrorCode::MethodNotFound,
+            format!("Method not found: {}", method),
+        ))
+    }
+}
+
+/// Handler that reports the directories of a [`WorkspaceContext`] as roots.
+/// This is what a `Client` uses when no other handler is registered.
+#[derive(Debug)]
+pub struct WorkspaceClientHandler {
+    workspace: Box<dyn WorkspaceContext + Send + Sync>,
+}
+
+impl WorkspaceClientHandler {
+    pub fn new(workspace: Box<dyn WorkspaceContext + Send + Sync>) -> Self {
+        Self { workspace }
+    }
+}
+
+impl Default for WorkspaceClientHandler {
+    fn default() -> Self {
+        Self::new(WorkspaceContextFactory::create_basic())
+    }
+}
+
+#[async_trait]
+impl ClientHandler for WorkspaceClientHandler {
+    async fn list_roots(&self) -> Result<serde_json::Value, Error> {
+        Ok(workspace_roots(self.workspace.as_ref()))
+    }
+}
+
+/// Builds a `roots/list` result from the directories of a workspace.
+pub fn workspace_roots(workspace: &dyn WorkspaceContext) -> serde_json::Value {
+    let roots = workspace
+        .get_directories()
+        .iter()
+        .map(|dir| {
+            let dir = dir.canonicalize().unwrap_or_else(|_| dir.clone());
+            Root {
+                uri: file_uri(&dir),
+                name: dir.file_name().map(|name| name.to_string_lossy().to_string()),
+            }
+        })
+        .collect();
+    serde_json::to_value(ListRootsResult { roots }).unwrap_or_default()
+}
+
+/// Turns a local path into a `file://` URI.
+fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        format!("file:///{}", path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace_context::CustomWorkspaceContext;
+    use std::path::PathBuf;
+
+    struct DefaultHandler;
+
+    impl ClientHandler for DefaultHandler {}
+
+    #[tokio::test]
+    async fn test_default_methods() {
+        let handler = DefaultHandler;
+        assert_eq!(
+            handler.handle_request("ping", None).await.unwrap(),
+            serde_json::json!({})
+        );
+        assert!(matches!(
+            handler.handle_request("sampling/createMessage", None).await,
+            Err(Error::Protocol { code: ErrorCode::MethodNotFound, .. })
+        ));
+        assert!(matches!(
+            handler.handle_request("unknown", None).await,
+            Err(Error::Protocol { code: ErrorCode::MethodNotFound, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_workspace_roots() {
+        let handler = WorkspaceClientHandler::new(Box::new(CustomWorkspaceContext::new(vec![
+            PathBuf::from("/nonexistent/project"),
+        ])));
+        let result = handler.handle_request("roots/list", None).await.unwrap();
+        assert_eq!(
+            result,
+            serde_json::json!({
+                "roots": [{"uri": "file:///nonexistent/project", "name": "project"}]
+            })
+        );
+    }
+}
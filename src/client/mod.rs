@@ -1,81 +1,184 @@
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
+use std::pin::Pin;
+use tokio::sync::{broadcast, Mutex, RwLock};
 use tokio::time::{Duration, timeout};
+pub use tokio_util::sync::CancellationToken;
 
 use crate::{
     ReadResourceResult,
     error::{Error, ErrorCode},
-    protocol::{Notification, Request, RequestId},
+    protocol::{Notification, Response, ResponseError},
+    requester::Requester,
     transport::{Message, Transport},
     types::{
-        CallToolRequest, CallToolResult, ClientCapabilities, Implementation, InitializeResult, 
-        ListResourcesResult, ListToolsResult, ServerCapabilities, Tool,
+        CallToolRequest, CallToolResult, ClientCapabilities, CompleteArgument, CompleteContext,
+        CompleteReference, CompleteRequest, CompleteResult, Completion, Cursor, GetPromptRequest,
+        GetPromptResult, Implementation, InitializeResult, ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult,
+        ListToolsResult, LoggingLevel, LoggingMessageNotification, PaginatedRequest, Progress,
+        ProgressToken, Prompt, RequestMeta, Resource, ResourceRequest, ResourceTemplate,
+        ResourceUpdatedNotification, ServerCapabilities, SetLevelRequest, Tool,
     },
 };
 
 mod builder;
 pub use builder::ClientBuilder;
 
+pub use crate::requester::RequestOptions;
+
+mod handler;
+pub use handler::{workspace_roots, ClientHandler, WorkspaceClientHandler};
+
 #[cfg(test)]
 mod test;
 
+/// How many notifications a slow subscriber may fall behind before it starts missing them.
+const NOTIFICATION_BUFFER_SIZE: usize = 256;
+
+/// An update from [`Client::call_tool_with_progress`].
+#[derive(Debug)]
+pub enum ToolCallUpdate {
+    /// The server reported progress on the call.
+    Progress(Progress),
+    /// The call completed; this is always the last update.
+    Finished(Result<CallToolResult, Error>),
+}
+
 /// The MCP client struct, managing transport, requests, and responses.
 /// This client is suitable for connecting to an MCP-compliant server to
 /// send requests, receive responses, and handle notifications.
+///
+/// Any number of requests can be in flight at once: a dispatcher task reads the
+/// transport and routes each response to the caller waiting on its request id.
 pub struct Client {
     /// The transport over which messages are sent/received.
     transport: Arc<dyn Transport>,
     /// The server's capabilities, populated after a successful initialize call.
     server_capabilities: Arc<RwLock<Option<ServerCapabilities>>>,
-    /// Request ID counter to generate unique IDs for each request.
-    request_counter: Arc<RwLock<i64>>,
-    /// An MPSC receiver for reading incoming responses from the transport.
-    response_receiver: Arc<Mutex<tokio::sync::mpsc::UnboundedReceiver<Message>>>,
-    /// An MPSC sender for sending responses from the transport handler to this client.
-    #[allow(dead_code)]
-    response_sender: tokio::sync::mpsc::UnboundedSender<Message>,
+    /// The protocol version agreed with the server during initialize.
+    protocol_version: RwLock<Option<String>>,
+    /// Requests sent to the server that are still waiting for a response.
+    requester: Requester,
+    /// Fan-out of notifications sent by the server to every subscriber.
+    notifications: broadcast::Sender<Notification>,
     /// To handle shutdown, in stdin/stdout case we also need to shut down subprocess
-    subprocess: Option<tokio::process::Child>,
+    subprocess: Mutex<Option<tokio::process::Child>>,
+    /// Task reading the transport; stopped when the client is dropped.
+    dispatcher: tokio::task::JoinHandle<()>,
+}
+
+/// Routes messages read from the transport: responses to waiting requests,
+/// notifications to subscribers and server requests to the client handler.
+struct Dispatcher {
+    transport: Arc<dyn Transport>,
+    requester: Requester,
+    notifications: broadcast::Sender<Notification>,
+    handler: Arc<dyn ClientHandler>,
+}
+
+impl Dispatcher {
+    /// Reads the transport until it closes or fails. A message that cannot be parsed
+    /// is logged and skipped, so one bad line does not strand every pending request.
+    async fn run(self) {
+        tracing::debug!("Starting response dispatcher task");
+        let mut stream = self.transport.receive();
+        while let Some(result) = stream.next().await {
+            match result {
+                Ok(message) => {
+                    tracing::trace!(?message, "Received message from transport");
+                    self.dispatch(message).await;
+                }
+                Err(Error::Serialization(e)) => {
+                    tracing::warn!("Skipping malformed message from transport: {}", e);
+                }
+                Err(e) => {
+                    tracing::error!(?e, "Error receiving message from transport");
+                    break;
+                }
+            }
+        }
+        self.requester.close().await;
+        tracing::debug!("Response dispatcher task terminated");
+    }
+
+    /// Routes a single incoming message.
+    async fn dispatch(&self, message: Message) {
+        match message {
+            Message::Response(response) => self.requester.resolve(response).await,
+            Message::Invalid(response) => {
+                tracing::warn!(?response, "Skipping invalid message from server");
+            }
+            Message::Batch(messages) => {
+                for message in messages {
+                    Box::pin(self.dispatch(message)).await;
+                }
+            }
+            Message::Notification(notif) => {
+                tracing::debug!(?notif, "Received notification");
+                // Having no subscribers is fine; the notification is simply dropped.
+                let _ = self.notifications.send(notif);
+            }
+            Message::Request(req) => {
+                tracing::debug!(?req, "Received request from server");
+                // Answer on a separate task so a slow handler never stalls responses.
+                let transport = self.transport.clone();
+                let handler = self.handler.clone();
+                tokio::spawn(async move {
+                    let id = req.id.clone();
+                    let response = match handler.handle_request(&req.method, req.params).await {
+                        Ok(result) => Response::success(id, Some(result)),
+                        Err(err) => Response::error(id, ResponseError::from(err)),
+                    };
+                    if let Err(e) = transport.send(Message::Response(response)).await {
+                        tracing::warn!(?e, "Failed to answer server request");
+                    }
+                });
+            }
+        }
+    }
 }
 
 impl Client {
     /// Creates a new MCP client with the given transport.
     /// This does not perform initialization. You typically call `client.initialize(...)` next.
+    ///
+    /// Requests from the server are answered by a [`WorkspaceClientHandler`] for the
+    /// current directory; use [`Client::with_handler`] to supply another handler.
     pub fn new(transport: Arc<dyn Transport>, subprocess: Option<tokio::process::Child>) -> Self {
-        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        let client = Self {
-            transport: transport.clone(),
-            server_capabilities: Arc::new(RwLock::new(None)),
-            request_counter: Arc::new(RwLock::new(0)),
-            response_receiver: Arc::new(Mutex::new(rx)),
-            response_sender: tx.clone(),
+        Self::with_handler(
+            transport,
             subprocess,
-        };
+            Arc::new(WorkspaceClientHandler::default()),
+        )
+    }
 
-        // Spawn a task to forward all transport messages into our MPSC channel.
-        let transport_clone = transport.clone();
-        let tx_clone = tx.clone();
-        tokio::spawn(async move {
-            tracing::debug!("Starting response handler task");
-            let mut stream = transport_clone.receive();
-            while let Some(result) = stream.next().await {
-                match result {
-                    Ok(message) => {
-                        tracing::trace!(?message, "Received message from transport");
-                        if tx_clone.send(message).is_err() {
-                            tracing::error!("Failed to forward message - channel closed");
-                            break;
-                        }
-                    }
-                    Err(e) => {
-                        tracing::error!(?e, "Error receiving message from transport");
-                        break;
-                    }
-                }
+    /// Creates a new MCP client whose server→client requests are answered by `handler`.
+    pub fn with_handler(
+        transport: Arc<dyn Transport>,
+        subprocess: Option<tokio::process::Child>,
+        handler: Arc<dyn ClientHandler>,
+    ) -> Self {
+        let requester = Requester::new(transport.clone());
+        let (notifications, _) = broadcast::channel(NOTIFICATION_BUFFER_SIZE);
+        let dispatcher = tokio::spawn(
+            Dispatcher {
+                transport: transport.clone(),
+                requester: requester.clone(),
+                notifications: notifications.clone(),
+                handler,
             }
-            tracing::debug!("Response handler task terminated");
-        });
+            .run(),
+        );
+        let client = Self {
+            transport,
+            server_capabilities: Arc::new(RwLock::new(None)),
+            protocol_version: RwLock::new(None),
+            requester,
+            notifications,
+            subprocess: Mutex::new(subprocess),
+            dispatcher,
+        };
 
         tracing::debug!("Created new MCP client");
         client
@@ -84,21 +187,49 @@ impl Client {
     /// Initializes the client by sending an "initialize" request containing:
     /// - client implementation info
     /// - client capabilities
-    /// - protocol version
+    /// - the latest protocol version this crate supports
     ///
-    /// On success, updates the client's `server_capabilities` field and sends an
-    /// `initialized` notification to the server.
+    /// See [`Client::initialize_with_version`] for how the version is negotiated.
     pub async fn initialize(
         &self,
         implementation: Implementation,
         capabilities: ClientCapabilities,
     ) -> Result<InitializeResult, Error> {
-        tracing::info!(?implementation, "Initializing MCP client");
+        self.initialize_with_version(implementation, capabilities, crate::LATEST_PROTOCOL_VERSION)
+            .await
+    }
+
+    /// Initializes the client, requesting `protocol_version`.
+    ///
+    /// The server may answer with a different version. If it is one of
+    /// [`SUPPORTED_PROTOCOL_VERSIONS`](crate::SUPPORTED_PROTOCOL_VERSIONS) the client
+    /// downgrades to it; otherwise initialization fails and no `initialized`
+    /// notification is sent. On success the client stores the server capabilities and
+    /// sends `notifications/initialized`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails, or if either side asks for a protocol
+    /// version this crate does not support.
+    pub async fn initialize_with_version(
+        &self,
+        implementation: Implementation,
+        capabilities: ClientCapabilities,
+        protocol_version: &str,
+    ) -> Result<InitializeResult, Error> {
+        tracing::info!(?implementation, %protocol_version, "Initializing MCP client");
+
+        if !crate::SUPPORTED_PROTOCOL_VERSIONS.contains(&protocol_version) {
+            return Err(Error::protocol(
+                ErrorCode::InvalidRequest,
+                format!("Unsupported protocol version requested: {}", protocol_version),
+            ));
+        }
 
         let params = serde_json::json!({
             "clientInfo": implementation,
             "capabilities": capabilities,
-            "protocolVersion": crate::LATEST_PROTOCOL_VERSION,
+            "protocolVersion": protocol_version,
         });
 
         let response = self.request("initialize", Some(params)).await?;
@@ -106,7 +237,29 @@ impl Client {
 
         tracing::debug!(?init_result, "Received initialization response");
 
-        // Store the server capabilities.
+        let negotiated = init_result.protocol_version.as_str();
+        if !crate::SUPPORTED_PROTOCOL_VERSIONS.contains(&negotiated) {
+            tracing::error!(%negotiated, "Server chose an unsupported protocol version");
+            return Err(Error::protocol(
+                ErrorCode::InvalidRequest,
+                format!("Server protocol version {} is not supported", negotiated),
+            )
+            .with_data(serde_json::json!({
+                "supported": crate::SUPPORTED_PROTOCOL_VERSIONS,
+                "requested": negotiated,
+            })));
+        }
+        if negotiated != protocol_version {
+            tracing::info!(
+                requested = %protocol_version,
+                %negotiated,
+                "Server downgraded protocol version"
+            );
+        }
+
+        // Store the negotiated version and the server capabilities.
+        *self.protocol_version.write().await = Some(negotiated.to_string());
+        self.transport.set_protocol_version(negotiated);
         *self.server_capabilities.write().await = Some(init_result.capabilities.clone());
 
         // After initialization completes, send the `initialized` notification.
@@ -117,79 +270,79 @@ impl Client {
         Ok(init_result)
     }
 
+    /// Returns the protocol version agreed with the server, once initialized.
+    pub async fn protocol_version(&self) -> Option<String> {
+        self.protocol_version.read().await.clone()
+    }
+
     /// Sends a request to the server with the given method and optional parameters,
-    /// then waits up to 30 seconds for a matching response.
+    /// then waits up to 120 seconds for a matching response. Other requests may be
+    /// sent and answered while this one is outstanding.
     ///
     /// # Errors
     ///
     /// Returns an error if the transport fails, the server returns an error,
-    /// or no response is received within 30 seconds.
+    /// or no response is received within 120 seconds.
     pub async fn request(
         &self,
         method: &str,
         params: Option<serde_json::Value>,
     ) -> Result<serde_json::Value, Error> {
-        // Increment request ID
-        let mut counter = self.request_counter.write().await;
-        *counter += 1;
-        let id = RequestId::Number(*counter);
-
-        let request = Request::new(method, params, id.clone());
-        tracing::debug!(?request, "Sending MCP request");
-
-        // Send request
-        self.transport.send(Message::Request(request)).await?;
-
-        // Wait for a matching response (by request ID) or a 120s timeout
-        let mut rx = self.response_receiver.lock().await;
-        match tokio::time::timeout(std::time::Duration::from_secs(120), async {
-            while let Some(message) = rx.recv().await {
-                match message {
-                    Message::Response(response) if response.id == id => {
-                        tracing::debug!(?response, "Received matching MCP response");
-                        if let Some(error) = response.error {
-                            tracing::error!(?error, "Server returned error");
-                            return Err(Error::Protocol {
-                                code: error.code.into(),
-                                message: error.message,
-                                data: error.data,
-                            });
-                        }
-                        return response.result.ok_or_else(|| {
-                            Error::protocol(ErrorCode::InternalError, "Response missing result")
-                        });
-                    }
-                    Message::Response(response) => {
-                        tracing::debug!(
-                            ?response,
-                            "Received non-matching response, continuing to wait"
-                        );
-                    }
-                    Message::Notification(notif) => {
-                        tracing::debug!(?notif, "Received notification while waiting for response");
-                    }
-                    Message::Request(req) => {
-                        tracing::debug!(?req, "Received request while waiting for response");
-                    }
-                }
-            }
+        self.request_with_options(method, params, RequestOptions::default())
+            .await
+    }
 
-            // Channel closed or no more messages.
-            Err(Error::protocol(
-                ErrorCode::InternalError,
-                "Connection closed while waiting for response",
-            ))
-        })
-        .await
-        {
-            Ok(result) => result,
-            Err(_) => {
-                tracing::error!("Request to '{}' timed out after 120 seconds", method);
-                Err(Error::Other(format!(
-                    "Request to '{method}' timed out after 120 seconds"
-                )))
-            }
+    /// Sends a request like [`Client::request`], with a custom timeout and an optional
+    /// cancellation token. If the request times out, is cancelled or its future is
+    /// dropped, the server is told to stop working on it with a
+    /// `notifications/cancelled` notification; `initialize` is never cancelled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transport fails, the server returns an error, the
+    /// timeout elapses, or the request is cancelled.
+    pub async fn request_with_options(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        options: RequestOptions,
+    ) -> Result<serde_json::Value, Error> {
+        self.requester.request(method, params, options).await
+    }
+
+    /// Sends several requests to the server as a single JSON-RPC batch and waits for
+    /// all of their responses. Results are returned in the same order as `requests`;
+    /// each entry holds either the result or the error returned for that request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transport fails, or if not every response arrives
+    /// within 120 seconds.
+    pub async fn request_batch(
+        &self,
+        requests: Vec<(String, Option<serde_json::Value>)>,
+    ) -> Result<Vec<Result<serde_json::Value, Error>>, Error> {
+        self.request_batch_with_options(requests, RequestOptions::default()).await
+    }
+
+    /// Sends a batch like [`Client::request_batch`], with the timeout and
+    /// cancellation in `options` applying to the batch as a whole. Requests still
+    /// waiting when it times out or is cancelled are cancelled on the server with
+    /// `notifications/cancelled`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transport fails, if not every response arrives in
+    /// time, or if the batch is cancelled.
+    pub async fn request_batch_with_options(
+        &self,
+        requests: Vec<(String, Option<serde_json::Value>)>,
+        options: RequestOptions,
+    ) -> Result<Vec<Result<serde_json::Value, Error>>, Error> {
+        if requests.is_empty() {
+            return Ok(Vec::new());
         }
+        self.requester.request_batch(requests, options).await
     }
 
     /// Sends a notification to the server using the given method and optional parameters.
@@ -206,6 +359,68 @@ impl Client {
             .await
     }
 
+    /// Returns a stream of every notification the server sends from now on, such as
+    /// `notifications/tools/list_changed` or `notifications/progress`.
+    ///
+    /// The stream ends when the client is dropped or the transport closes. A subscriber that falls far behind
+    /// skips the oldest notifications rather than blocking the client.
+    pub fn subscribe_notifications(&self) -> Pin<Box<dyn Stream<Item = Notification> + Send>> {
+        let mut rx = self.notifications.subscribe();
+        Box::pin(async_stream::stream! {
+            loop {
+                match rx.recv().await {
+                    Ok(notification) => yield notification,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "Notification subscriber lagged behind");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Like [`Client::subscribe_notifications`], but only yields notifications whose
+    /// method equals `method`, e.g. `notifications/resources/updated`.
+    pub fn subscribe_notifications_for(
+        &self,
+        method: impl Into<String>,
+    ) -> Pin<Box<dyn Stream<Item = Notification> + Send>> {
+        let method = method.into();
+        Box::pin(
+            self.subscribe_notifications()
+                .filter(move |notification| futures::future::ready(notification.method == method)),
+        )
+    }
+
+    /// Asks the server to send log messages at `level` and above by calling `logging/setLevel`.
+    ///
+    /// Only meaningful if the server advertises the `logging` capability.
+    pub async fn set_log_level(&self, level: LoggingLevel) -> Result<(), Error> {
+        tracing::debug!(?level, "Setting server log level");
+        let params = serde_json::to_value(SetLevelRequest { level })?;
+        self.request("logging/setLevel", Some(params)).await?;
+        Ok(())
+    }
+
+    /// Returns a stream of every `notifications/message` log message the server sends.
+    pub fn log_messages(
+        &self,
+    ) -> Pin<Box<dyn Stream<Item = LoggingMessageNotification> + Send>> {
+        Box::pin(
+            self.subscribe_notifications_for("notifications/message")
+                .filter_map(|notification| async move {
+                    let params = notification.params?;
+                    match serde_json::from_value(params) {
+                        Ok(message) => Some(message),
+                        Err(e) => {
+                            tracing::warn!(%e, "Malformed log message notification");
+                            None
+                        }
+                    }
+                }),
+        )
+    }
+
     /// Returns the cached server capabilities if the client has already initialized.
     pub async fn capabilities(&self) -> Option<ServerCapabilities> {
         let caps = self.server_capabilities.read().await.clone();
@@ -214,10 +429,10 @@ impl Client {
     }
 
     /// Shuts down the client by closing the transport. This does not send a server shutdown request.
-    pub async fn shutdown(&mut self) -> Result<(), Error> {
+    pub async fn shutdown(&self) -> Result<(), Error> {
         tracing::info!("Shutting down MCP client");
         self.transport.close().await?;
-        if let Some(mut child) = self.subprocess.take() {
+        if let Some(mut child) = self.subprocess.lock().await.take() {
             const TIMEOUT: u64 = 2;
             if let Ok(None) = child.try_wait() {
                 tracing::info!("Have an associated subprocess, waiting {}s", TIMEOUT);
@@ -234,12 +449,17 @@ impl Client {
     }
 
     /// Lists available tools on the server by calling `tools/list`.
+    ///
+    /// Only the first page is returned; use [`Client::list_tools_paginated`] to
+    /// fetch every tool.
     pub async fn list_tools(&self) -> Result<ListToolsResult, Error> {
-        tracing::debug!("Listing available tools");
-        let response = self.request("tools/list", None).await?;
-        let result = serde_json::from_value(response).map_err(Error::from);
-        tracing::debug!(?result, "Received tools list");
-        result
+        self.list_page("tools/list", None).await
+    }
+
+    /// Streams every tool on the server, requesting further pages of `tools/list`
+    /// as long as the server returns a `nextCursor`.
+    pub fn list_tools_paginated(&self) -> Pin<Box<dyn Stream<Item = Result<Tool, Error>> + Send + '_>> {
+        self.paginate::<ListToolsResult>("tools/list")
     }
 
     /// Calls a tool on the server by name, passing the specified arguments as JSON.
@@ -249,11 +469,76 @@ impl Client {
         &self,
         name: &str,
         arguments: serde_json::Value,
+    ) -> Result<CallToolResult, Error> {
+        self.call_tool_with_meta(name, arguments, None).await
+    }
+
+    /// Like [`Client::call_tool`], but asks the server to report progress.
+    ///
+    /// The returned stream yields a [`ToolCallUpdate::Progress`] for every
+    /// `notifications/progress` the server sends for this call, then ends with a single
+    /// [`ToolCallUpdate::Finished`] carrying the result.
+    pub fn call_tool_with_progress(
+        &self,
+        name: &str,
+        arguments: serde_json::Value,
+    ) -> Pin<Box<dyn Stream<Item = ToolCallUpdate> + Send + '_>> {
+        let token = ProgressToken::String(uuid::Uuid::new_v4().to_string());
+        let name = name.to_string();
+        // Listen before calling so that progress sent right away is not missed.
+        let target = token.clone();
+        let mut progress = Box::pin(self.progress_updates().filter(move |progress| {
+            futures::future::ready(progress.progress_token == target)
+        }));
+        let meta = RequestMeta {
+            progress_token: Some(token),
+        };
+        Box::pin(async_stream::stream! {
+            let call = self.call_tool_with_meta(&name, arguments, Some(meta));
+            tokio::pin!(call);
+            loop {
+                tokio::select! {
+                    // Progress is delivered before the response, so drain it first.
+                    biased;
+                    Some(update) = progress.next() => yield ToolCallUpdate::Progress(update),
+                    result = &mut call => {
+                        yield ToolCallUpdate::Finished(result);
+                        break;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Returns a stream of every `notifications/progress` notification, for all requests.
+    pub fn progress_updates(&self) -> Pin<Box<dyn Stream<Item = Progress> + Send>> {
+        Box::pin(
+            self.subscribe_notifications_for("notifications/progress")
+                .filter_map(|notification| async move {
+                    let params = notification.params?;
+                    match serde_json::from_value(params) {
+                        Ok(progress) => Some(progress),
+                        Err(e) => {
+                            tracing::warn!(%e, "Malformed progress notification");
+                            None
+                        }
+                    }
+                }),
+        )
+    }
+
+    /// Calls `tools/call`, attaching `meta` as the request's `_meta`.
+    async fn call_tool_with_meta(
+        &self,
+        name: &str,
+        arguments: serde_json::Value,
+        meta: Option<RequestMeta>,
     ) -> Result<CallToolResult, Error> {
         tracing::debug!(%name, ?arguments, "Calling tool");
         let request = CallToolRequest {
             name: name.to_string(),
             arguments,
+            meta,
         };
 
         let response = self
@@ -263,22 +548,10 @@ impl Client {
         let tool_result: CallToolResult = serde_json::from_value(response)?;
         if tool_result.is_error {
             // We treat tool-level errors (isError=true) as a Rust error.
-            let maybe_msg = tool_result
-                .content
-                .iter()
-                .filter_map(|msg| {
-                    if let crate::types::MessageContent::Text { text } = msg {
-                        Some(text.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join("\n");
-
             return Err(Error::Other(format!(
                 "Tool '{}' execution failed: {}",
-                name, maybe_msg
+                name,
+                tool_result.text()
             )));
         }
         tracing::debug!(?tool_result, "Tool call succeeded");
@@ -288,28 +561,236 @@ impl Client {
     /// Retrieves a single tool from the server by name, returning `Some(tool)` if found, or `None` otherwise.
     pub async fn get_tool(&self, name: &str) -> Result<Option<Tool>, Error> {
         tracing::debug!(%name, "Getting specific tool");
-        let tools = self.list_tools().await?;
-        let tool = tools.tools.into_iter().find(|t| t.name == name);
-        tracing::debug!(?tool, "Found tool");
-        Ok(tool)
+        let mut tools = self.list_tools_paginated();
+        while let Some(tool) = tools.next().await {
+            let tool = tool?;
+            if tool.name == name {
+                tracing::debug!(?tool, "Found tool");
+                return Ok(Some(tool));
+            }
+        }
+        Ok(None)
     }
 
     /// Reads a resource by URI from the server, calling `resources/read`.
     pub async fn read_resource(&self, uri: &str) -> Result<ReadResourceResult, Error> {
         tracing::debug!(%uri, "Reading resource");
-        let params = serde_json::json!({ "uri": uri });
+        let params = serde_json::to_value(ResourceRequest { uri: uri.to_string() })?;
         let response = self.request("resources/read", Some(params)).await?;
         let result = serde_json::from_value(response).map_err(Error::from);
         tracing::debug!(?result, "Received resource content");
         result
     }
 
-    /// Lists resources by calling `resources/list` on the server.
+    /// Lists resources by calling `resources/list` on the server (first page only).
     pub async fn list_resources(&self) -> Result<ListResourcesResult, Error> {
-        tracing::debug!("Listing available resources");
-        let response = self.request("resources/list", None).await?;
+        self.list_page("resources/list", None).await
+    }
+
+    /// Streams every resource on the server, following `nextCursor`.
+    pub fn list_resources_paginated(
+        &self,
+    ) -> Pin<Box<dyn Stream<Item = Result<Resource, Error>> + Send + '_>> {
+        self.paginate::<ListResourcesResult>("resources/list")
+    }
+
+    /// Subscribes to changes of the resource at `uri` by calling `resources/subscribe`.
+    ///
+    /// The returned stream yields every `notifications/resources/updated` the server
+    /// sends for `uri`; call [`Client::read_resource`] to fetch the new contents.
+    /// Dropping the stream does not unsubscribe, use [`Client::unsubscribe_resource`].
+    pub async fn subscribe_resource(
+        &self,
+        uri: &str,
+    ) -> Result<Pin<Box<dyn Stream<Item = ResourceUpdatedNotification> + Send>>, Error> {
+        tracing::debug!(%uri, "Subscribing to resource");
+        // Listen before subscribing so that an update sent right away is not missed.
+        let target = uri.to_string();
+        let updates = self
+            .resource_updates()
+            .filter(move |update| futures::future::ready(update.uri == target));
+        let params = serde_json::to_value(ResourceRequest { uri: uri.to_string() })?;
+        self.request("resources/subscribe", Some(params)).await?;
+        Ok(Box::pin(updates))
+    }
+
+    /// Stops receiving updates for `uri` by calling `resources/unsubscribe`.
+    pub async fn unsubscribe_resource(&self, uri: &str) -> Result<(), Error> {
+        tracing::debug!(%uri, "Unsubscribing from resource");
+        let params = serde_json::to_value(ResourceRequest { uri: uri.to_string() })?;
+        self.request("resources/unsubscribe", Some(params)).await?;
+        Ok(())
+    }
+
+    /// Returns a stream of every `notifications/resources/updated` notification,
+    /// for all subscribed resources.
+    pub fn resource_updates(
+        &self,
+    ) -> Pin<Box<dyn Stream<Item = ResourceUpdatedNotification> + Send>> {
+        Box::pin(
+            self.subscribe_notifications_for("notifications/resources/updated")
+                .filter_map(|notification| async move {
+                    let params = notification.params?;
+                    match serde_json::from_value(params) {
+                        Ok(update) => Some(update),
+                        Err(e) => {
+                            tracing::warn!(%e, "Malformed resource update notification");
+                            None
+                        }
+                    }
+                }),
+        )
+    }
+
+    /// Lists resource templates by calling `resources/templates/list` (first page only).
+    pub async fn list_resource_templates(&self) -> Result<ListResourceTemplatesResult, Error> {
+        self.list_page("resources/templates/list", None).await
+    }
+
+    /// Streams every resource template on the server, following `nextCursor`.
+    pub fn list_resource_templates_paginated(
+        &self,
+    ) -> Pin<Box<dyn Stream<Item = Result<ResourceTemplate, Error>> + Send + '_>> {
+        self.paginate::<ListResourceTemplatesResult>("resources/templates/list")
+    }
+
+    /// Lists prompts by calling `prompts/list` on the server (first page only).
+    pub async fn list_prompts(&self) -> Result<ListPromptsResult, Error> {
+        self.list_page("prompts/list", None).await
+    }
+
+    /// Streams every prompt on the server, following `nextCursor`.
+    pub fn list_prompts_paginated(
+        &self,
+    ) -> Pin<Box<dyn Stream<Item = Result<Prompt, Error>> + Send + '_>> {
+        self.paginate::<ListPromptsResult>("prompts/list")
+    }
+
+    /// Fetches a prompt by calling `prompts/get`, filling in its template with `arguments`.
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: HashMap<String, String>,
+    ) -> Result<GetPromptResult, Error> {
+        tracing::debug!(%name, ?arguments, "Getting prompt");
+        let request = GetPromptRequest {
+            name: name.to_string(),
+            arguments: (!arguments.is_empty()).then_some(arguments),
+        };
+        let response = self
+            .request("prompts/get", Some(serde_json::to_value(request)?))
+            .await?;
         let result = serde_json::from_value(response).map_err(Error::from);
-        tracing::debug!(?result, "Received resources list");
+        tracing::debug!(?result, "Received prompt");
         result
     }
+
+    /// Asks the server for completions of an argument by calling `completion/complete`.
+    ///
+    /// `reference` names the prompt or resource template the argument belongs to, and
+    /// `context` carries the values of arguments that were already filled in.
+    pub async fn complete(
+        &self,
+        reference: CompleteReference,
+        argument: CompleteArgument,
+        context: Option<CompleteContext>,
+    ) -> Result<Completion, Error> {
+        tracing::debug!(?reference, ?argument, "Requesting completion");
+        let request = CompleteRequest {
+            reference,
+            argument,
+            context,
+        };
+        let response = self
+            .request("completion/complete", Some(serde_json::to_value(request)?))
+            .await?;
+        let result: CompleteResult = serde_json::from_value(response)?;
+        Ok(result.completion)
+    }
+
+    /// Requests a single page of a `*/list` method, starting at `cursor`.
+    async fn list_page<P: serde::de::DeserializeOwned + std::fmt::Debug>(
+        &self,
+        method: &str,
+        cursor: Option<Cursor>,
+    ) -> Result<P, Error> {
+        tracing::debug!(%method, ?cursor, "Listing page");
+        let params = match cursor {
+            Some(cursor) => Some(serde_json::to_value(PaginatedRequest { cursor: Some(cursor) })?),
+            None => None,
+        };
+        let response = self.request(method, params).await?;
+        let result = serde_json::from_value(response).map_err(Error::from);
+        tracing::debug!(?result, "Received page");
+        result
+    }
+
+    /// Yields the items of every page of `method`, stopping at the first error or
+    /// when the server stops returning a cursor.
+    fn paginate<P: Page>(
+        &self,
+        method: &'static str,
+    ) -> Pin<Box<dyn Stream<Item = Result<P::Item, Error>> + Send + '_>> {
+        Box::pin(async_stream::try_stream! {
+            let mut cursor = None;
+            loop {
+                let page: P = self.list_page(method, cursor.clone()).await?;
+                let (items, next_cursor) = page.into_parts();
+                for item in items {
+                    yield item;
+                }
+                // A server handing back the cursor it was given would loop forever.
+                if next_cursor.is_none() || next_cursor == cursor {
+                    break;
+                }
+                cursor = next_cursor;
+            }
+        })
+    }
+}
+
+impl Drop for Client {
+    /// Stops reading the transport, which also ends every notification stream.
+    fn drop(&mut self) {
+        self.dispatcher.abort();
+    }
+}
+
+/// A page of a paginated `*/list` result.
+trait Page: serde::de::DeserializeOwned + std::fmt::Debug + Send + 'static {
+    type Item: Send + 'static;
+
+    fn into_parts(self) -> (Vec<Self::Item>, Option<Cursor>);
+}
+
+impl Page for ListToolsResult {
+    type Item = Tool;
+
+    fn into_parts(self) -> (Vec<Tool>, Option<Cursor>) {
+        (self.tools, self.next_cursor)
+    }
+}
+
+impl Page for ListResourcesResult {
+    type Item = Resource;
+
+    fn into_parts(self) -> (Vec<Resource>, Option<Cursor>) {
+        (self.resources, self.next_cursor)
+    }
+}
+
+impl Page for ListResourceTemplatesResult {
+    type Item = ResourceTemplate;
+
+    fn into_parts(self) -> (Vec<ResourceTemplate>, Option<Cursor>) {
+        (self.resource_templates, self.next_cursor)
+    }
+}
+
+impl Page for ListPromptsResult {
+    type Item = Prompt;
+
+    fn into_parts(self) -> (Vec<Prompt>, Option<Cursor>) {
+        (self.prompts, self.next_cursor)
+    }
 }
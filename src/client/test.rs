@@ -0,0 +1,727 @@
+use super::*;
+use crate::protocol::{Request, RequestId, Response};
+use crate::transport::http::{HttpClientTransport, HttpServerTransport, DEFAULT_ENDPOINT_PATH};
+use crate::transport::memory::duplex;
+
+#[tokio::test]
+async fn test_client_list_tools_over_http() {
+    let server = Arc::new(HttpServerTransport::bind(([127, 0, 0, 1], 0)).await.unwrap());
+    let endpoint = format!("http://{}/{}", server.local_addr(), DEFAULT_ENDPOINT_PATH);
+
+    // A stand-in server that answers `tools/list` with a single tool.
+    let responder = server.clone();
+    tokio::spawn(async move {
+        let mut stream = responder.receive();
+        while let Some(Ok(message)) = stream.next().await {
+            if let Message::Request(request) = message {
+                let result = serde_json::json!({
+                    "tools": [{
+                        "name": "echo",
+                        "description": "Echoes its input",
+                        "inputSchema": {"type": "object"}
+                    }]
+                });
+                let _ = responder
+                    .send(Message::Response(Response::success(request.id, Some(result))))
+                    .await;
+            }
+        }
+    });
+
+    let client = Client::new(Arc::new(HttpClientTransport::new(endpoint)), None);
+    let tools = client.list_tools().await.unwrap();
+    assert_eq!(tools.tools.len(), 1);
+    assert_eq!(tools.tools[0].name, "echo");
+
+    server.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_client_request_over_memory_transport() {
+    let (client_side, server_side) = duplex();
+
+    // Echo the request params back as the result.
+    tokio::spawn(async move {
+        let mut stream = server_side.receive();
+        while let Some(Ok(message)) = stream.next().await {
+            if let Message::Request(request) = message {
+                let _ = server_side
+                    .send(Message::Response(Response::success(request.id, request.params)))
+                    .await;
+            }
+        }
+    });
+
+    let client = Client::new(Arc::new(client_side), None);
+    let result = client
+        .request("echo", Some(serde_json::json!({"value": 42})))
+        .await
+        .unwrap();
+    assert_eq!(result, serde_json::json!({"value": 42}));
+}
+
+#[tokio::test]
+async fn test_client_request_batch() {
+    let (client_side, server_side) = duplex();
+
+    // Answer a batch with a batch, in reverse order, failing the `fail` method.
+    tokio::spawn(async move {
+        let mut stream = server_side.receive();
+        while let Some(Ok(message)) = stream.next().await {
+            if let Message::Batch(messages) = message {
+                let responses = messages
+                    .into_iter()
+                    .rev()
+                    .filter_map(|message| match message {
+                        Message::Request(request) if request.method == "fail" => {
+                            Some(Message::Response(Response::error(
+                                request.id,
+                                crate::protocol::ResponseError {
+                                    code: ErrorCode::MethodNotFound.into(),
+                                    message: "Method not found".to_string(),
+                                    data: None,
+                                },
+                            )))
+                        }
+                        Message::Request(request) => Some(Message::Response(Response::success(
+                            request.id,
+                            Some(serde_json::json!({"method": request.method})),
+                        ))),
+                        _ => None,
+                    })
+                    .collect();
+                let _ = server_side.send(Message::Batch(responses)).await;
+            }
+        }
+    });
+
+    let client = Client::new(Arc::new(client_side), None);
+    let results = client
+        .request_batch(vec![
+            ("tools/list".to_string(), None),
+            ("fail".to_string(), None),
+            ("resources/list".to_string(), None),
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(
+        results[0].as_ref().unwrap(),
+        &serde_json::json!({"method": "tools/list"})
+    );
+    assert!(matches!(
+        results[1],
+        Err(Error::Protocol { code: ErrorCode::MethodNotFound, .. })
+    ));
+    assert_eq!(
+        results[2].as_ref().unwrap(),
+        &serde_json::json!({"method": "resources/list"})
+    );
+}
+
+#[tokio::test]
+async fn test_client_concurrent_requests() {
+    let (client_side, server_side) = duplex();
+    let server_side = Arc::new(server_side);
+
+    // Answer `slow` only after `fast` has been answered, so the responses arrive out of order.
+    let responder = server_side.clone();
+    tokio::spawn(async move {
+        let mut stream = responder.receive();
+        let mut slow = None;
+        while let Some(Ok(message)) = stream.next().await {
+            if let Message::Request(request) = message {
+                if request.method == "slow" {
+                    slow = Some(request.id);
+                    continue;
+                }
+                let _ = responder
+                    .send(Message::Response(Response::success(
+                        request.id,
+                        Some(serde_json::json!("fast")),
+                    )))
+                    .await;
+                if let Some(id) = slow.take() {
+                    let _ = responder
+                        .send(Message::Response(Response::success(
+                            id,
+                            Some(serde_json::json!("slow")),
+                        )))
+                        .await;
+                }
+            }
+        }
+    });
+
+    let client = Arc::new(Client::new(Arc::new(client_side), None));
+    let slow_client = client.clone();
+    let slow = tokio::spawn(async move { slow_client.request("slow", None).await });
+
+    // Give the slow request time to reach the server first.
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let fast = timeout(Duration::from_secs(5), client.request("fast", None))
+        .await
+        .expect("fast request blocked behind slow one")
+        .unwrap();
+    assert_eq!(fast, serde_json::json!("fast"));
+    assert_eq!(slow.await.unwrap().unwrap(), serde_json::json!("slow"));
+}
+
+#[tokio::test]
+async fn test_client_request_fails_when_connection_closes() {
+    let (client_side, server_side) = duplex();
+    let client = Client::new(Arc::new(client_side), None);
+
+    let mut stream = server_side.receive();
+    let request = client.request("never-answered", None);
+    let close = async {
+        assert!(matches!(stream.next().await, Some(Ok(Message::Request(_)))));
+        server_side.close().await.unwrap();
+    };
+    let (result, _) = tokio::join!(request, close);
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_client_skips_malformed_messages() {
+    use crate::transport::stdio::StdioTransport;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (client_io, server_io) = tokio::io::duplex(4096);
+    let (client_read, client_write) = tokio::io::split(client_io);
+    let transport = StdioTransport::with_streams(client_read, client_write).unwrap();
+    let client = Arc::new(Client::new(Arc::new(transport), None));
+
+    let first = tokio::spawn({
+        let client = client.clone();
+        async move { client.request("first", None).await }
+    });
+    let second = tokio::spawn({
+        let client = client.clone();
+        async move { client.request("second", None).await }
+    });
+
+    // Wait for both requests, then answer them with a garbage line in between.
+    let (server_read, mut server_write) = tokio::io::split(server_io);
+    let mut lines = BufReader::new(server_read).lines();
+    let mut ids = Vec::new();
+    while ids.len() < 2 {
+        let line = lines.next_line().await.unwrap().unwrap();
+        let request: Request = serde_json::from_str(&line).unwrap();
+        ids.push((request.method, request.id));
+    }
+    let mut replies = String::new();
+    for (i, (method, id)) in ids.into_iter().enumerate() {
+        if i == 1 {
+            replies.push_str("{this is not json}\n");
+        }
+        let response = Response::success(id, Some(serde_json::json!(method)));
+        replies.push_str(&serde_json::to_string(&response).unwrap());
+        replies.push('\n');
+    }
+    server_write.write_all(replies.as_bytes()).await.unwrap();
+
+    let first = timeout(Duration::from_secs(5), first).await.unwrap().unwrap();
+    let second = timeout(Duration::from_secs(5), second).await.unwrap().unwrap();
+    assert_eq!(first.unwrap(), serde_json::json!("first"));
+    assert_eq!(second.unwrap(), serde_json::json!("second"));
+}
+
+/// Reads messages from `stream` until a `notifications/cancelled` arrives and returns its params.
+async fn next_cancellation(
+    stream: &mut (impl futures::Stream<Item = Result<Message, Error>> + Unpin),
+) -> serde_json::Value {
+    while let Some(Ok(message)) = stream.next().await {
+        if let Message::Notification(notification) = message {
+            if notification.method == "notifications/cancelled" {
+                return notification.params.unwrap();
+            }
+        }
+    }
+    panic!("Transport closed without a cancellation notification");
+}
+
+#[tokio::test]
+async fn test_client_request_cancellation() {
+    let (client_side, server_side) = duplex();
+    let client = Arc::new(Client::new(Arc::new(client_side), None));
+    let mut stream = server_side.receive();
+
+    let token = CancellationToken::new();
+    let options = RequestOptions::default().cancellation(token.clone());
+    let requester = client.clone();
+    let call =
+        tokio::spawn(async move { requester.request_with_options("slow", None, options).await });
+
+    let id = match stream.next().await {
+        Some(Ok(Message::Request(request))) => request.id,
+        other => panic!("Expected request, got {:?}", other),
+    };
+    token.cancel();
+
+    assert!(matches!(call.await.unwrap(), Err(Error::Cancelled(_))));
+    let params = next_cancellation(&mut stream).await;
+    assert_eq!(params["requestId"], serde_json::to_value(&id).unwrap());
+}
+
+#[tokio::test]
+async fn test_client_request_timeout_sends_cancellation() {
+    let (client_side, server_side) = duplex();
+    let client = Client::new(Arc::new(client_side), None);
+    let mut stream = server_side.receive();
+
+    let options = RequestOptions::default().timeout(Duration::from_millis(50));
+    let result = client.request_with_options("slow", None, options).await;
+    assert!(matches!(result, Err(Error::Other(_))));
+
+    assert!(matches!(stream.next().await, Some(Ok(Message::Request(_)))));
+    let params = next_cancellation(&mut stream).await;
+    assert_eq!(params["requestId"], serde_json::json!(1));
+    assert_eq!(params["reason"], serde_json::json!("timeout"));
+}
+
+#[tokio::test]
+async fn test_client_dropped_request_sends_cancellation() {
+    let (client_side, server_side) = duplex();
+    let client = Client::new(Arc::new(client_side), None);
+    let mut stream = server_side.receive();
+
+    // The request loses the race and its future is dropped mid-flight.
+    tokio::select! {
+        _ = client.request("slow", None) => panic!("The request should not complete"),
+        _ = tokio::time::sleep(Duration::from_millis(50)) => {}
+    }
+
+    assert!(matches!(stream.next().await, Some(Ok(Message::Request(_)))));
+    let params = next_cancellation(&mut stream).await;
+    assert_eq!(params["requestId"], serde_json::json!(1));
+}
+
+#[tokio::test]
+async fn test_client_does_not_cancel_initialize() {
+    let (client_side, server_side) = duplex();
+    let client = Client::new(Arc::new(client_side), None);
+    let mut stream = server_side.receive();
+
+    let options = RequestOptions::default().timeout(Duration::from_millis(50));
+    let result = client.request_with_options("initialize", None, options).await;
+    assert!(matches!(result, Err(Error::Other(_))));
+
+    assert!(matches!(stream.next().await, Some(Ok(Message::Request(_)))));
+    assert!(timeout(Duration::from_millis(200), stream.next()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_client_request_batch_timeout_sends_cancellations() {
+    let (client_side, server_side) = duplex();
+    let client = Client::new(Arc::new(client_side), None);
+    let mut stream = server_side.receive();
+
+    let options = RequestOptions::default().timeout(Duration::from_millis(50));
+    let result = client
+        .request_batch_with_options(
+            vec![("slow".to_string(), None), ("slow".to_string(), None)],
+            options,
+        )
+        .await;
+    assert!(matches!(result, Err(Error::Other(_))));
+
+    assert!(matches!(stream.next().await, Some(Ok(Message::Batch(_)))));
+    for id in [1, 2] {
+        let params = next_cancellation(&mut stream).await;
+        assert_eq!(params["requestId"], serde_json::json!(id));
+        assert_eq!(params["reason"], serde_json::json!("timeout"));
+    }
+}
+
+#[tokio::test]
+async fn test_client_http_request_against_hanging_server() {
+    // A server that accepts connections and reads requests but never answers them.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}/{}", listener.local_addr().unwrap(), DEFAULT_ENDPOINT_PATH);
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            connections.push(stream);
+        }
+    });
+    let client = Client::new(Arc::new(HttpClientTransport::new(endpoint)), None);
+
+    let options = RequestOptions::default().timeout(Duration::from_millis(100));
+    let result = timeout(
+        Duration::from_secs(5),
+        client.request_with_options("slow", None, options),
+    )
+    .await
+    .expect("timeout did not fire while the POST was hanging");
+    assert!(matches!(result, Err(Error::Other(_))));
+
+    let token = CancellationToken::new();
+    let options = RequestOptions::default().no_timeout().cancellation(token.clone());
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        canceller.cancel();
+    });
+    let result = timeout(
+        Duration::from_secs(5),
+        client.request_with_options("slow", None, options),
+    )
+    .await
+    .expect("cancellation did not fire while the POST was hanging");
+    assert!(matches!(result, Err(Error::Cancelled(_))));
+}
+
+#[tokio::test]
+async fn test_client_notification_subscriptions() {
+    let (client_side, server_side) = duplex();
+    let client = Client::new(Arc::new(client_side), None);
+
+    let mut all = client.subscribe_notifications();
+    let mut progress = client.subscribe_notifications_for("notifications/progress");
+
+    for method in ["notifications/tools/list_changed", "notifications/progress"] {
+        server_side
+            .send(Message::Notification(Notification::new(method, None)))
+            .await
+            .unwrap();
+    }
+
+    let first = timeout(Duration::from_secs(5), all.next()).await.unwrap().unwrap();
+    assert_eq!(first.method, "notifications/tools/list_changed");
+    let second = timeout(Duration::from_secs(5), all.next()).await.unwrap().unwrap();
+    assert_eq!(second.method, "notifications/progress");
+
+    let filtered = timeout(Duration::from_secs(5), progress.next()).await.unwrap().unwrap();
+    assert_eq!(filtered.method, "notifications/progress");
+
+    // Notifications sent in a batch are delivered too.
+    server_side
+        .send(Message::Batch(vec![Message::Notification(Notification::new(
+            "notifications/message",
+            Some(serde_json::json!({"level": "info", "data": "hello"})),
+        ))]))
+        .await
+        .unwrap();
+    let logged = timeout(Duration::from_secs(5), all.next()).await.unwrap().unwrap();
+    assert_eq!(logged.method, "notifications/message");
+
+    // Dropping the client ends the streams even though the transport stays open.
+    drop(client);
+    assert!(timeout(Duration::from_secs(5), all.next()).await.unwrap().is_none());
+    assert!(timeout(Duration::from_secs(5), progress.next()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_client_answers_server_requests() {
+    struct SamplingHandler;
+
+    #[async_trait::async_trait]
+    impl ClientHandler for SamplingHandler {
+        async fn create_message(
+            &self,
+            _params: serde_json::Value,
+        ) -> Result<serde_json::Value, Error> {
+            Ok(serde_json::json!({
+                "role": "assistant",
+                "content": {"type": "text", "text": "hi"},
+                "model": "test"
+            }))
+        }
+    }
+
+    let (client_side, server_side) = duplex();
+    let _client = Client::with_handler(Arc::new(client_side), None, Arc::new(SamplingHandler));
+    let mut stream = server_side.receive();
+
+    for (id, method) in [(1, "ping"), (2, "sampling/createMessage"), (3, "roots/list")] {
+        server_side
+            .send(Message::Request(Request::new(method, None, RequestId::Number(id))))
+            .await
+            .unwrap();
+        match timeout(Duration::from_secs(5), stream.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => {
+                assert_eq!(response.id, RequestId::Number(id));
+                assert!(response.error.is_none(), "{} failed: {:?}", method, response.error);
+                if method == "roots/list" {
+                    assert!(response.result.unwrap()["roots"].is_array());
+                }
+            }
+            other => panic!("Expected response to {}, got {:?}", method, other),
+        }
+    }
+}
+
+/// Spawns a stand-in server that answers `initialize` with `protocol_version` and
+/// forwards every other message it receives to the returned channel.
+fn spawn_initialize_responder(
+    server_side: crate::transport::memory::MemoryTransport,
+    protocol_version: &'static str,
+) -> tokio::sync::mpsc::UnboundedReceiver<Message> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut stream = server_side.receive();
+        while let Some(Ok(message)) = stream.next().await {
+            match message {
+                Message::Request(request) if request.method == "initialize" => {
+                    let result = serde_json::json!({
+                        "protocolVersion": protocol_version,
+                        "serverInfo": {"name": "test-server", "version": "1.0.0"},
+                        "capabilities": {"completions": {}}
+                    });
+                    let _ = server_side
+                        .send(Message::Response(Response::success(request.id, Some(result))))
+                        .await;
+                }
+                other => {
+                    let _ = tx.send(other);
+                }
+            }
+        }
+    });
+    rx
+}
+
+fn test_implementation() -> Implementation {
+    Implementation {
+        name: "test-client".to_string(),
+        version: "0.1.0".to_string(),
+        title: None,
+    }
+}
+
+#[tokio::test]
+async fn test_initialize_accepts_supported_downgrade() {
+    let (client_side, server_side) = duplex();
+    let mut received = spawn_initialize_responder(server_side, "2024-11-05");
+
+    let client = Client::new(Arc::new(client_side), None);
+    let result = client
+        .initialize(test_implementation(), ClientCapabilities::default())
+        .await
+        .unwrap();
+    assert_eq!(result.protocol_version, "2024-11-05");
+    assert_eq!(client.protocol_version().await.as_deref(), Some("2024-11-05"));
+    assert!(client.capabilities().await.unwrap().completions.is_some());
+
+    match timeout(Duration::from_secs(5), received.recv()).await {
+        Ok(Some(Message::Notification(n))) => assert_eq!(n.method, "notifications/initialized"),
+        other => panic!("Expected initialized notification, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_initialize_rejects_unsupported_version() {
+    let (client_side, server_side) = duplex();
+    let mut received = spawn_initialize_responder(server_side, "1999-01-01");
+
+    let client = Client::new(Arc::new(client_side), None);
+    let result = client
+        .initialize(test_implementation(), ClientCapabilities::default())
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::Protocol { code: ErrorCode::InvalidRequest, .. })
+    ));
+    assert!(client.protocol_version().await.is_none());
+
+    // The handshake must not be completed.
+    assert!(timeout(Duration::from_millis(100), received.recv()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_paginated_lists_follow_next_cursor() {
+    let (client_side, server_side) = duplex();
+
+    // Serves three pages of tools and a single page of prompts.
+    tokio::spawn(async move {
+        let mut stream = server_side.receive();
+        while let Some(Ok(message)) = stream.next().await {
+            let Message::Request(request) = message else { continue };
+            let cursor = request
+                .params
+                .as_ref()
+                .and_then(|params| params.get("cursor"))
+                .and_then(|cursor| cursor.as_str())
+                .map(str::to_string);
+            let result = match (request.method.as_str(), cursor.as_deref()) {
+                ("tools/list", None) => serde_json::json!({
+                    "tools": [{"name": "a", "inputSchema": {"type": "object"}},
+                              {"name": "b", "inputSchema": {"type": "object"}}],
+                    "nextCursor": "page-2"
+                }),
+                ("tools/list", Some("page-2")) => serde_json::json!({
+                    "tools": [{"name": "c", "inputSchema": {"type": "object"}}],
+                    "nextCursor": "page-3"
+                }),
+                ("tools/list", Some("page-3")) => serde_json::json!({
+                    "tools": [{"name": "d", "inputSchema": {"type": "object"}}]
+                }),
+                ("prompts/list", None) => serde_json::json!({
+                    "prompts": [{"name": "review", "arguments": [{"name": "code", "required": true}]}]
+                }),
+                _ => serde_json::json!({}),
+            };
+            let _ = server_side
+                .send(Message::Response(Response::success(request.id, Some(result))))
+                .await;
+        }
+    });
+
+    let client = Client::new(Arc::new(client_side), None);
+
+    let first_page = client.list_tools().await.unwrap();
+    assert_eq!(first_page.tools.len(), 2);
+    assert_eq!(first_page.next_cursor.as_deref(), Some("page-2"));
+
+    let names: Vec<String> = client
+        .list_tools_paginated()
+        .map(|tool| tool.unwrap().name)
+        .collect()
+        .await;
+    assert_eq!(names, ["a", "b", "c", "d"]);
+
+    let tool = client.get_tool("d").await.unwrap();
+    assert_eq!(tool.map(|tool| tool.name).as_deref(), Some("d"));
+    assert!(client.get_tool("missing").await.unwrap().is_none());
+
+    let prompts: Vec<Prompt> = client
+        .list_prompts_paginated()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(prompts.len(), 1);
+    assert_eq!(prompts[0].name, "review");
+}
+
+#[tokio::test]
+async fn test_resource_subscription_updates() {
+    let (client_side, server_side) = duplex();
+    let (methods_tx, mut methods) = tokio::sync::mpsc::unbounded_channel();
+
+    // Acknowledges (un)subscriptions and then reports changes to two resources.
+    tokio::spawn(async move {
+        let mut stream = server_side.receive();
+        while let Some(Ok(Message::Request(request))) = stream.next().await {
+            let _ = methods_tx.send((request.method.clone(), request.params.clone()));
+            let _ = server_side
+                .send(Message::Response(Response::success(request.id, Some(serde_json::json!({})))))
+                .await;
+            if request.method == "resources/subscribe" {
+                for uri in ["memory://other", "file:///watched.txt"] {
+                    let _ = server_side
+                        .send(Message::Notification(Notification::new(
+                            "notifications/resources/updated",
+                            Some(serde_json::json!({ "uri": uri })),
+                        )))
+                        .await;
+                }
+            }
+        }
+    });
+
+    let client = Client::new(Arc::new(client_side), None);
+    let mut all_updates = client.resource_updates();
+    let mut updates = client.subscribe_resource("file:///watched.txt").await.unwrap();
+
+    let update = timeout(Duration::from_secs(5), updates.next()).await.unwrap().unwrap();
+    assert_eq!(update.uri, "file:///watched.txt");
+    let first = timeout(Duration::from_secs(5), all_updates.next()).await.unwrap().unwrap();
+    assert_eq!(first.uri, "memory://other");
+
+    client.unsubscribe_resource("file:///watched.txt").await.unwrap();
+    let expected_params = Some(serde_json::json!({"uri": "file:///watched.txt"}));
+    assert_eq!(
+        methods.recv().await.unwrap(),
+        ("resources/subscribe".to_string(), expected_params.clone())
+    );
+    assert_eq!(
+        methods.recv().await.unwrap(),
+        ("resources/unsubscribe".to_string(), expected_params)
+    );
+}
+
+#[tokio::test]
+async fn test_set_log_level_and_log_messages() {
+    let (client_side, server_side) = duplex();
+
+    // Acknowledges `logging/setLevel`, then logs one message at the requested level.
+    tokio::spawn(async move {
+        let mut stream = server_side.receive();
+        while let Some(Ok(Message::Request(request))) = stream.next().await {
+            assert_eq!(request.method, "logging/setLevel");
+            let level = request.params.unwrap()["level"].clone();
+            let _ = server_side
+                .send(Message::Response(Response::success(request.id, Some(serde_json::json!({})))))
+                .await;
+            let _ = server_side
+                .send(Message::Notification(Notification::new(
+                    "notifications/message",
+                    Some(serde_json::json!({"level": level, "logger": "payments", "data": "sent"})),
+                )))
+                .await;
+        }
+    });
+
+    let client = Client::new(Arc::new(client_side), None);
+    let mut messages = client.log_messages();
+    client.set_log_level(LoggingLevel::Warning).await.unwrap();
+
+    let message = timeout(Duration::from_secs(5), messages.next()).await.unwrap().unwrap();
+    assert_eq!(message.level, LoggingLevel::Warning);
+    assert_eq!(message.logger.as_deref(), Some("payments"));
+    assert_eq!(message.data, serde_json::json!("sent"));
+}
+
+#[tokio::test]
+async fn test_call_tool_with_progress() {
+    let (client_side, server_side) = duplex();
+
+    // Reports two steps of progress for the request's token, then returns the result.
+    tokio::spawn(async move {
+        let mut stream = server_side.receive();
+        while let Some(Ok(Message::Request(request))) = stream.next().await {
+            let params = request.params.unwrap();
+            assert_eq!(params["name"], "transfer");
+            let token = params["_meta"]["progressToken"].clone();
+            for step in [1, 2] {
+                let _ = server_side
+                    .send(Message::Notification(Notification::new(
+                        "notifications/progress",
+                        Some(serde_json::json!({"progressToken": token, "progress": step, "total": 2})),
+                    )))
+                    .await;
+            }
+            let result = serde_json::json!({"content": [{"type": "text", "text": "done"}]});
+            let _ = server_side
+                .send(Message::Response(Response::success(request.id, Some(result))))
+                .await;
+        }
+    });
+
+    let client = Client::new(Arc::new(client_side), None);
+    let updates: Vec<_> = timeout(
+        Duration::from_secs(5),
+        client
+            .call_tool_with_progress("transfer", serde_json::json!({}))
+            .collect::<Vec<_>>(),
+    )
+    .await
+    .unwrap();
+
+    let progress: Vec<f64> = updates
+        .iter()
+        .filter_map(|update| match update {
+            ToolCallUpdate::Progress(progress) => Some(progress.progress),
+            ToolCallUpdate::Finished(_) => None,
+        })
+        .collect();
+    assert_eq!(progress, vec![1.0, 2.0]);
+    match updates.last() {
+        Some(ToolCallUpdate::Finished(Ok(result))) => {
+            assert_eq!(result.content[0].as_text(), Some("done"));
+        }
+        other => panic!("Expected a successful result last, got {:?}", other),
+    }
+}
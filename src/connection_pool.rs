@@ -1,26 +1,57 @@
-use crate::client::{Client, ClientBuilder};
-use crate::types::ClientCapabilities;
+use crate::client::{Client, ClientBuilder, ClientHandler, ToolCallUpdate};
+use crate::prompt_registry::{DiscoveredMcpPrompt, PromptRegistry};
+use crate::types::{
+    CallToolResult, ClientCapabilities, LoggingLevel, LoggingMessageNotification, Progress,
+    RootsCapability, Tool,
+};
+use futures::{StreamExt, TryStreamExt};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{broadcast, RwLock};
 use anyhow::Result;
-use tracing::{info, debug, warn};
+use tracing::{info, debug, warn, error};
 
 /// 连接池管理器
 pub struct ConnectionPool {
     /// 活跃的连接映射
-    connections: Arc<RwLock<HashMap<String, Arc<Mutex<Client>>>>>,
+    connections: Arc<RwLock<HashMap<String, Arc<Client>>>>,
     /// 连接配置
     configs: Arc<RwLock<HashMap<String, McpServerConfig>>>,
+    /// 为每个服务器创建客户端处理器（应答采样、roots等服务器请求）
+    handler_factory: Arc<RwLock<Option<ClientHandlerFactory>>>,
+    /// 通过连接池调用的工具的进度广播
+    tool_progress: broadcast::Sender<ToolProgress>,
 }
 
+/// 工具调用进度
+#[derive(Debug, Clone)]
+pub struct ToolProgress {
+    /// 工具所属服务器
+    pub server_name: String,
+    /// 工具名称
+    pub tool_name: String,
+    /// 服务器报告的进度
+    pub progress: Progress,
+}
+
+/// 根据服务器名称创建客户端处理器的工厂
+pub type ClientHandlerFactory = Arc<dyn Fn(&str) -> Arc<dyn ClientHandler> + Send + Sync>;
+
 /// MCP服务器配置
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct McpServerConfig {
+    #[serde(default)]
     pub command: String,
+    #[serde(default)]
     pub args: Vec<String>,
     pub directory: Option<String>,
     pub env: Option<HashMap<String, String>>,
+    /// 已运行服务器的地址（如 `tcp://host:port`、`unix:///path`），设置后不再启动子进程
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// 请求服务器转发的最低日志级别，未设置时使用服务器默认值
+    #[serde(default, rename = "logLevel", skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<LoggingLevel>,
 }
 
 impl ConnectionPool {
@@ -29,9 +60,16 @@ impl ConnectionPool {
         Self {
             connections: Arc::new(RwLock::new(HashMap::new())),
             configs: Arc::new(RwLock::new(HashMap::new())),
+            handler_factory: Arc::new(RwLock::new(None)),
+            tool_progress: broadcast::channel(64).0,
         }
     }
 
+    /// 设置客户端处理器工厂，之后新建的连接将由它应答服务器发来的请求（如 `sampling/createMessage`）
+    pub async fn set_client_handler_factory(&self, factory: ClientHandlerFactory) {
+        *self.handler_factory.write().await = Some(factory);
+    }
+
     /// 注册服务器配置
     pub async fn register_server(&self, name: String, config: McpServerConfig) {
         let name_clone = name.clone();
@@ -41,7 +79,7 @@ impl ConnectionPool {
     }
 
     /// 获取或创建连接
-    pub async fn get_connection(&self, server_name: &str) -> Result<Arc<Mutex<Client>>> {
+    pub async fn get_connection(&self, server_name: &str) -> Result<Arc<Client>> {
         // 首先检查是否已有活跃连接
         {
             let connections = self.connections.read().await;
@@ -65,7 +103,7 @@ impl ConnectionPool {
         let client = self.create_client(server_name, config).await?;
         
         // 将新连接添加到池中
-        let client_arc = Arc::new(Mutex::new(client));
+        let client_arc = Arc::new(client);
         {
             let mut connections = self.connections.write().await;
             connections.insert(server_name.to_string(), client_arc.clone());
@@ -101,23 +139,74 @@ impl ConnectionPool {
         // 设置客户端实现信息
         builder = builder.implementation("mcp-connection-pool", "0.1.0");
         
-        // 设置客户端能力
-        let capabilities = ClientCapabilities {
-            custom: None,
+        // 设置客户端能力；配置了处理器时声明支持采样
+        let mut capabilities = ClientCapabilities {
+            roots: Some(RootsCapability::default()),
+            ..Default::default()
         };
+        if let Some(factory) = self.handler_factory.read().await.as_ref() {
+            builder = builder.handler(factory(name));
+            capabilities.sampling = Some(HashMap::new());
+        }
         builder = builder.capabilities(capabilities);
+
+        // 初始化前就开始转发服务器日志，避免丢失启动阶段的日志
+        let server_name = name.to_string();
+        builder = builder.on_log_message(move |message| log_server_message(&server_name, message));
         
-        let client = builder.spawn_and_initialize().await?;
+        let client = match &config.url {
+            Some(url) => builder.connect(url).await?,
+            None => builder.spawn_and_initialize().await?,
+        };
         debug!("成功创建客户端连接: {}", name);
+
+        let supports_logging = client
+            .capabilities()
+            .await
+            .is_some_and(|capabilities| capabilities.logging.is_some());
+        if let (Some(level), true) = (config.log_level, supports_logging) {
+            if let Err(e) = client.set_log_level(level).await {
+                warn!("设置服务器 {} 日志级别失败: {}", name, e);
+            }
+        }
         
         Ok(client)
     }
 
+    /// 调用指定服务器上的工具，执行期间服务器报告的进度会广播给 [`ConnectionPool::subscribe_tool_progress`] 的订阅者
+    pub async fn call_tool(
+        &self,
+        server_name: &str,
+        tool_name: &str,
+        arguments: serde_json::Value,
+    ) -> Result<CallToolResult> {
+        let client = self.get_connection(server_name).await?;
+        let mut updates = client.call_tool_with_progress(tool_name, arguments);
+        while let Some(update) = updates.next().await {
+            match update {
+                ToolCallUpdate::Progress(progress) => {
+                    // 没有订阅者时直接丢弃
+                    let _ = self.tool_progress.send(ToolProgress {
+                        server_name: server_name.to_string(),
+                        tool_name: tool_name.to_string(),
+                        progress,
+                    });
+                }
+                ToolCallUpdate::Finished(result) => return Ok(result?),
+            }
+        }
+        Err(anyhow::anyhow!("工具 {} 调用未返回结果", tool_name))
+    }
+
+    /// 订阅之后通过连接池调用的所有工具的进度
+    pub fn subscribe_tool_progress(&self) -> broadcast::Receiver<ToolProgress> {
+        self.tool_progress.subscribe()
+    }
+
     /// 关闭指定连接
     pub async fn close_connection(&self, server_name: &str) -> Result<()> {
         let mut connections = self.connections.write().await;
-        if let Some(client_arc) = connections.remove(server_name) {
-            let mut client = client_arc.lock().await;
+        if let Some(client) = connections.remove(server_name) {
             client.shutdown().await?;
             info!("已关闭连接: {}", server_name);
         }
@@ -127,8 +216,7 @@ impl ConnectionPool {
     /// 关闭所有连接
     pub async fn close_all_connections(&self) -> Result<()> {
         let mut connections = self.connections.write().await;
-        for (name, client_arc) in connections.drain() {
-            let mut client = client_arc.lock().await;
+        for (name, client) in connections.drain() {
             if let Err(e) = client.shutdown().await {
                 warn!("关闭连接 {} 时出错: {}", name, e);
             } else {
@@ -150,6 +238,85 @@ impl ConnectionPool {
         configs.keys().cloned().collect()
     }
 
+    /// 发现指定服务器提供的提示（自动跟随分页游标）
+    ///
+    /// 服务器未声明 `prompts` 能力时返回空列表
+    pub async fn discover_prompts(&self, server_name: &str) -> Result<Vec<DiscoveredMcpPrompt>> {
+        let client = self.get_connection(server_name).await?;
+        let supports_prompts = client
+            .capabilities()
+            .await
+            .is_some_and(|capabilities| capabilities.prompts.is_some());
+        if !supports_prompts {
+            debug!("服务器 {} 不支持提示", server_name);
+            return Ok(Vec::new());
+        }
+
+        let prompts: Vec<_> = client.list_prompts_paginated().try_collect().await?;
+        debug!("服务器 {} 提供 {} 个提示", server_name, prompts.len());
+        Ok(prompts
+            .into_iter()
+            .map(|prompt| {
+                DiscoveredMcpPrompt::from_client(prompt, server_name.to_string(), client.clone())
+            })
+            .collect())
+    }
+
+    /// 发现所有已注册服务器的提示并刷新到注册表中，返回注册的提示数量
+    ///
+    /// 单个服务器发现失败时保留其原有提示，不影响其他服务器
+    pub async fn discover_all_prompts(&self, registry: &mut PromptRegistry) -> usize {
+        let mut count = 0;
+        for server_name in self.list_registered_servers().await {
+            match self.discover_prompts(&server_name).await {
+                Ok(prompts) => {
+                    registry.remove_prompts_by_server(&server_name);
+                    count += prompts.len();
+                    for prompt in prompts {
+                        registry.register_prompt(prompt);
+                    }
+                }
+                Err(e) => warn!("发现服务器 {} 的提示失败: {}", server_name, e),
+            }
+        }
+        count
+    }
+
+    /// 列出指定服务器提供的工具（自动跟随分页游标）
+    ///
+    /// 服务器未声明 `tools` 能力时返回空列表
+    pub async fn list_tools(&self, server_name: &str) -> Result<Vec<Tool>> {
+        let client = self.get_connection(server_name).await?;
+        let supports_tools = client
+            .capabilities()
+            .await
+            .is_some_and(|capabilities| capabilities.tools.is_some());
+        if !supports_tools {
+            debug!("服务器 {} 不支持工具", server_name);
+            return Ok(Vec::new());
+        }
+
+        let tools: Vec<_> = client.list_tools_paginated().try_collect().await?;
+        debug!("服务器 {} 提供 {} 个工具", server_name, tools.len());
+        Ok(tools)
+    }
+
+    /// 列出所有已注册服务器的工具，返回 `(服务器名称, 工具)` 列表
+    ///
+    /// 单个服务器列出失败时跳过，不影响其他服务器
+    pub async fn list_all_tools(&self) -> Vec<(String, Tool)> {
+        let mut all_tools = Vec::new();
+        for server_name in self.list_registered_servers().await {
+            match self.list_tools(&server_name).await {
+                Ok(tools) => {
+                    all_tools.extend(tools.into_iter().map(|tool| (server_name.clone(), tool)));
+                }
+                Err(e) => warn!("列出服务器 {} 的工具失败: {}", server_name, e),
+            }
+        }
+        all_tools
+    }
+
     /// 显示连接池状态
     pub async fn show_pool_status(&self) {
         let connections = self.connections.read().await;
@@ -159,13 +326,13 @@ impl ConnectionPool {
         info!("已配置服务器: {}", configs.len());
         info!("活跃连接数: {}", connections.len());
         
-        for (name, _) in &*connections {
+        for name in connections.keys() {
             let is_healthy = self.is_connection_healthy(name).await;
             let status = if is_healthy { "✅ 健康" } else { "❌ 不健康" };
             info!("  - {}: {}", name, status);
         }
         
-        for (name, _) in &*configs {
+        for name in configs.keys() {
             if !connections.contains_key(name) {
                 info!("  - {}: ⏸️  未连接", name);
             }
@@ -175,14 +342,10 @@ impl ConnectionPool {
 
     /// 检查连接是否健康
     pub async fn is_connection_healthy(&self, server_name: &str) -> bool {
-        let connections = self.connections.read().await;
-        if let Some(client_arc) = connections.get(server_name) {
-            let client = client_arc.lock().await;
-            // 尝试列出工具来检查连接是否还活跃
-            match client.list_tools().await {
-                Ok(_) => true,
-                Err(_) => false,
-            }
+        let client = self.connections.read().await.get(server_name).cloned();
+        if let Some(client) = client {
+            // 尝试列出工具来检查连接是否还活跃；客户端支持并发请求，不会阻塞其他调用
+            client.list_tools().await.is_ok()
         } else {
             false
         }
@@ -210,6 +373,23 @@ impl ConnectionPool {
     }
 }
 
+/// 将服务器发来的 `notifications/message` 按对应级别写入 tracing
+fn log_server_message(server_name: &str, message: LoggingMessageNotification) {
+    let logger = message.logger.as_deref().unwrap_or_default();
+    let data = match &message.data {
+        serde_json::Value::String(text) => text.clone(),
+        data => data.to_string(),
+    };
+    match message.level {
+        LoggingLevel::Debug => debug!(server = %server_name, logger, "{}", data),
+        LoggingLevel::Info | LoggingLevel::Notice => {
+            info!(server = %server_name, logger, "{}", data)
+        }
+        LoggingLevel::Warning => warn!(server = %server_name, logger, "{}", data),
+        _ => error!(server = %server_name, logger, "{}", data),
+    }
+}
+
 impl Default for ConnectionPool {
     fn default() -> Self {
         Self::new()
@@ -223,3 +403,18 @@ impl Drop for ConnectionPool {
         info!("ConnectionPool正在被销毁，请确保已关闭所有连接");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_only_config_deserializes() {
+        // 连接已运行的服务器时只需提供地址
+        let config: McpServerConfig =
+            serde_json::from_str(r#"{"url":"tcp://127.0.0.1:9000"}"#).unwrap();
+        assert_eq!(config.url.as_deref(), Some("tcp://127.0.0.1:9000"));
+        assert!(config.command.is_empty());
+        assert!(config.args.is_empty());
+    }
+}
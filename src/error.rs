@@ -100,6 +100,10 @@ pub enum Error {
     #[error("IO error: {0}")]
     Io(String),
 
+    /// The request was cancelled before a response arrived
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+
     /// Other miscellaneous errors
     #[error("Other error: {0}")]
     Other(String),
@@ -4,14 +4,14 @@
 //! 提供强大的上下文感知和工具调用能力。
 //!
 //! 这个库提供了完整的MCP协议实现，支持AI模型与其运行环境之间的通信。
-//! 支持客户端和服务器实现，通过stdio传输层进行通信。
+//! 支持客户端和服务器实现，通过stdio、HTTP或WebSocket传输层进行通信。
 //!
 //! 项目地址: https://github.com/your-username/alou-rust
 //!
 //! ## Features
 //!
 //! - Full implementation of MCP protocol specification
-//! - Stdio transport layer
+//! - Stdio, Streamable HTTP and WebSocket transport layers
 //! - Async/await support using Tokio
 //! - Type-safe message handling
 //! - Comprehensive error handling
@@ -20,8 +20,8 @@
 //!
 //! ```no_run
 //! use std::sync::Arc;
-//! use mcp_client_rs::client::Client;
-//! use mcp_client_rs::transport::stdio::StdioTransport;
+//! use alou::client::Client;
+//! use alou::transport::stdio::StdioTransport;
 //! use tokio::io::{stdin, stdout};
 //!
 //! #[tokio::main]
@@ -30,7 +30,7 @@
 //!     let transport = StdioTransport::with_streams(stdin(), stdout())?;
 //!     
 //!     // Create the client with Arc-wrapped transport
-//!     let client = Client::new(Arc::new(transport));
+//!     let client = Client::new(Arc::new(transport), None);
 //!     
 //!     // Use the client...
 //!     
@@ -52,13 +52,15 @@ pub mod connection_pool;
 pub mod error;
 /// Protocol-specific types and implementations
 pub mod protocol;
+/// Request/response bookkeeping shared by the client and the server's peer
+mod requester;
 /// Prompt registry for managing MCP prompts
 pub mod prompt_registry;
 /// System prompts and templates
 pub mod prompts;
 /// Server module provides the MCP server implementation
 pub mod server;
-/// Transport layer implementations (stdio)
+/// Transport layer implementations (stdio, TCP/Unix sockets, HTTP, WebSocket, in-memory)
 pub mod transport;
 /// Common types used throughout the SDK
 pub mod types;
@@ -84,14 +86,15 @@ pub use types::*;
 ///
 /// This version represents the most recent protocol specification that this SDK supports.
 /// It is used during client-server handshake to ensure compatibility.
-pub const LATEST_PROTOCOL_VERSION: &str = "2024-11-05";
+pub const LATEST_PROTOCOL_VERSION: &str = "2025-06-18";
 
 /// List of all protocol versions supported by this SDK
 ///
 /// This list is used during version negotiation to determine compatibility between
 /// client and server. The versions are listed in order of preference, with the
 /// most recent version first.
-pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &[LATEST_PROTOCOL_VERSION, "2024-10-07"];
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] =
+    &[LATEST_PROTOCOL_VERSION, "2025-03-26", "2024-11-05", "2024-10-07"];
 
 /// JSON-RPC version used by the MCP protocol
 ///
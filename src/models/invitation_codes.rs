@@ -96,7 +96,7 @@ impl InvitationCode {
             "#,
             )
                 .bind(&invitation_code.code)
-                .bind(&invitation_code.expires_at)
+                .bind(invitation_code.expires_at)
                 .fetch_one(pool)
                 .await?;
 
@@ -1,7 +1,17 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
 use anyhow::Result;
 
+use crate::client::Client;
+use crate::types::{
+    CompleteArgument, CompleteContext, CompleteReference, MessageContent, PromptMessage, Role,
+};
+
+pub use crate::types::GetPromptResult;
+
 /// MCP提示结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Prompt {
@@ -10,20 +20,31 @@ pub struct Prompt {
     pub arguments: Option<Vec<serde_json::Value>>,
 }
 
-/// 获取提示结果
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GetPromptResult {
-    pub description: Option<String>,
-    pub messages: Vec<serde_json::Value>,
-}
+/// 提示调用函数：接收参数，异步返回提示内容
+pub type PromptInvokeFn = Arc<
+    dyn Fn(HashMap<String, serde_json::Value>) -> Pin<Box<dyn Future<Output = Result<GetPromptResult>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// 参数补全函数：接收参数名、已输入的值和已填写的其他参数，异步返回候选值
+pub type PromptCompleteFn = Arc<
+    dyn Fn(String, String, HashMap<String, String>) -> Pin<Box<dyn Future<Output = Result<Vec<String>>> + Send>>
+        + Send
+        + Sync,
+>;
 
 /// 发现的MCP提示
+#[derive(Clone)]
 pub struct DiscoveredMcpPrompt {
     pub name: String,
     pub description: Option<String>,
     pub arguments: Option<Vec<serde_json::Value>>,
     pub server_name: String,
-    pub invoke: Box<dyn Fn(HashMap<String, serde_json::Value>) -> Result<GetPromptResult> + Send + Sync>,
+    /// 克隆后仍指向同一个调用函数（及其所属的客户端）
+    invoker: PromptInvokeFn,
+    /// 未设置时不提供参数补全
+    completer: Option<PromptCompleteFn>,
 }
 
 impl DiscoveredMcpPrompt {
@@ -32,36 +53,105 @@ impl DiscoveredMcpPrompt {
         description: Option<String>,
         arguments: Option<Vec<serde_json::Value>>,
         server_name: String,
-        invoke: Box<dyn Fn(HashMap<String, serde_json::Value>) -> Result<GetPromptResult> + Send + Sync>,
+        invoker: PromptInvokeFn,
     ) -> Self {
         Self {
             name,
             description,
             arguments,
             server_name,
-            invoke,
+            invoker,
+            completer: None,
         }
     }
-}
 
-impl Clone for DiscoveredMcpPrompt {
-    fn clone(&self) -> Self {
-        // 由于invoke函数无法克隆，我们创建一个默认的实现
-        Self {
-            name: self.name.clone(),
-            description: self.description.clone(),
-            arguments: self.arguments.clone(),
-            server_name: self.server_name.clone(),
-            invoke: Box::new(|_params: HashMap<String, serde_json::Value>| {
-                Ok(GetPromptResult {
-                    description: Some("Cloned prompt".to_string()),
-                    messages: vec![],
-                })
-            }),
+    /// 设置参数补全函数
+    pub fn with_completer(mut self, completer: PromptCompleteFn) -> Self {
+        self.completer = Some(completer);
+        self
+    }
+
+    /// 根据服务器返回的提示定义创建，调用时通过所属客户端发送 `prompts/get`，
+    /// 补全参数时发送 `completion/complete`
+    pub fn from_client(prompt: crate::types::Prompt, server_name: String, client: Arc<Client>) -> Self {
+        let prompt_name = prompt.name.clone();
+        let completer_client = client.clone();
+        let completer_prompt_name = prompt.name.clone();
+        let invoker: PromptInvokeFn = Arc::new(move |params: HashMap<String, serde_json::Value>| {
+            let client = client.clone();
+            let prompt_name = prompt_name.clone();
+            Box::pin(async move {
+                // prompts/get 的参数值均为字符串
+                let arguments = params
+                    .into_iter()
+                    .map(|(key, value)| match value {
+                        serde_json::Value::String(value) => (key, value),
+                        value => (key, value.to_string()),
+                    })
+                    .collect();
+                Ok(client.get_prompt(&prompt_name, arguments).await?)
+            })
+        });
+
+        let arguments = prompt.arguments.map(|arguments| {
+            arguments
+                .iter()
+                .filter_map(|argument| serde_json::to_value(argument).ok())
+                .collect()
+        });
+        let completer: PromptCompleteFn = Arc::new(move |argument: String, value: String, context: HashMap<String, String>| {
+            let client = completer_client.clone();
+            let reference = CompleteReference::Prompt {
+                name: completer_prompt_name.clone(),
+                title: None,
+            };
+            Box::pin(async move {
+                let context = (!context.is_empty()).then_some(CompleteContext {
+                    arguments: Some(context),
+                });
+                let completion = client
+                    .complete(reference, CompleteArgument { name: argument, value }, context)
+                    .await?;
+                Ok(completion.values)
+            })
+        });
+
+        Self::new(prompt.name, prompt.description, arguments, server_name, invoker)
+            .with_completer(completer)
+    }
+
+    /// 调用提示，获取填充参数后的消息
+    pub async fn invoke(&self, params: HashMap<String, serde_json::Value>) -> Result<GetPromptResult> {
+        (self.invoker)(params).await
+    }
+
+    /// 补全参数 `argument` 的候选值，`context` 为已填写的其他参数
+    ///
+    /// 未设置补全函数时返回空列表
+    pub async fn complete(
+        &self,
+        argument: &str,
+        value: &str,
+        context: HashMap<String, String>,
+    ) -> Result<Vec<String>> {
+        match &self.completer {
+            Some(completer) => completer(argument.to_string(), value.to_string(), context).await,
+            None => Ok(Vec::new()),
         }
     }
 }
 
+impl std::fmt::Debug for DiscoveredMcpPrompt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiscoveredMcpPrompt")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("arguments", &self.arguments)
+            .field("server_name", &self.server_name)
+            .finish_non_exhaustive()
+    }
+}
+
 /// 提示注册表
 #[derive(Clone)]
 pub struct PromptRegistry {
@@ -230,9 +320,9 @@ impl PromptFactory {
         description: Option<String>,
         arguments: Option<Vec<serde_json::Value>>,
         server_name: String,
-        invoke: Box<dyn Fn(HashMap<String, serde_json::Value>) -> Result<GetPromptResult> + Send + Sync>,
+        invoker: PromptInvokeFn,
     ) -> DiscoveredMcpPrompt {
-        DiscoveredMcpPrompt::new(name, description, arguments, server_name, invoke)
+        DiscoveredMcpPrompt::new(name, description, arguments, server_name, invoker)
     }
 
     /// 创建简单的提示
@@ -243,14 +333,15 @@ impl PromptFactory {
     ) -> DiscoveredMcpPrompt {
         let name_clone = name.clone();
         let description_clone = description.clone();
-        let invoke = Box::new(move |_params: HashMap<String, serde_json::Value>| {
-            Ok(GetPromptResult {
+        let invoker: PromptInvokeFn = Arc::new(move |_params: HashMap<String, serde_json::Value>| {
+            let result = GetPromptResult {
                 description: Some(description_clone.clone()),
-                messages: vec![serde_json::json!({
-                    "role": "user",
-                    "content": format!("执行提示: {}", name_clone)
-                })],
-            })
+                messages: vec![PromptMessage {
+                    role: Role::User,
+                    content: MessageContent::text(format!("执行提示: {}", name_clone)),
+                }],
+            };
+            Box::pin(async move { Ok(result) })
         });
 
         DiscoveredMcpPrompt::new(
@@ -258,7 +349,7 @@ impl PromptFactory {
             Some(description),
             None,
             server_name,
-            invoke,
+            invoker,
         )
     }
 }
@@ -401,6 +492,49 @@ mod tests {
         assert_eq!(registry.prompt_count(), 1);
     }
 
+    #[tokio::test]
+    async fn test_cloned_registry_invokes_through_client() {
+        use crate::protocol::Response;
+        use crate::transport::{memory::duplex, Message, Transport};
+        use futures::StreamExt;
+
+        let (client_side, server_side) = duplex();
+        tokio::spawn(async move {
+            let mut stream = server_side.receive();
+            while let Some(Ok(Message::Request(request))) = stream.next().await {
+                assert_eq!(request.method, "prompts/get");
+                let params = request.params.unwrap();
+                let result = serde_json::json!({
+                    "messages": [{
+                        "role": "user",
+                        "content": {
+                            "type": "text",
+                            "text": format!("{} {} {}", params["name"], params["arguments"]["code"], params["arguments"]["lines"])
+                        }
+                    }]
+                });
+                let _ = server_side
+                    .send(Message::Response(Response::success(request.id, Some(result))))
+                    .await;
+            }
+        });
+        let client = Arc::new(Client::new(Arc::new(client_side), None));
+
+        let prompt: crate::types::Prompt =
+            serde_json::from_value(serde_json::json!({"name": "review", "arguments": [{"name": "code"}]}))
+                .unwrap();
+        let mut registry = PromptRegistry::new();
+        registry.register_prompt(DiscoveredMcpPrompt::from_client(prompt, "server".to_string(), client));
+
+        let cloned = registry.clone();
+        let params = HashMap::from([
+            ("code".to_string(), serde_json::json!("fn main() {}")),
+            ("lines".to_string(), serde_json::json!(3)),
+        ]);
+        let result = cloned.get_prompt("review").unwrap().invoke(params).await.unwrap();
+        assert_eq!(result.messages[0].content.as_text(), Some(r#""review" "fn main() {}" "3""#));
+    }
+
     #[test]
     fn test_prompt_utils() {
         assert!(PromptUtils::validate_prompt_name("valid_name").is_ok());
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::env;
 use anyhow::{Result, Context};
@@ -23,11 +23,7 @@ pub struct SystemPromptConfig {
 
 /// 标准化URL，移除尾部斜杠以进行一致比较
 fn normalize_url(url: &str) -> String {
-    if url.ends_with('/') {
-        url[..url.len() - 1].to_string()
-    } else {
-        url.to_string()
-    }
+    url.strip_suffix('/').unwrap_or(url).to_string()
 }
 
 /// 检查URL是否匹配数组中的任何URL，忽略尾部斜杠
@@ -46,7 +42,7 @@ pub fn get_core_system_prompt(
     let mut system_md_enabled = false;
     let mut system_md_path = PathBuf::from(GEMINI_CONFIG_DIR).join("system.md");
     
-    if let Some(system_md_var) = env::var("GEMINI_SYSTEM_MD").ok() {
+    if let Ok(system_md_var) = env::var("GEMINI_SYSTEM_MD") {
         let system_md_var_lower = system_md_var.to_lowercase();
         if !["0", "false"].contains(&system_md_var_lower.as_str()) {
             system_md_enabled = true; // 启用系统提示覆盖
@@ -125,7 +121,7 @@ pub fn get_core_system_prompt(
     };
 
     // 如果设置了GEMINI_WRITE_SYSTEM_MD（且不是0|false），将基础系统提示写入文件
-    if let Some(write_system_md_var) = env::var("GEMINI_WRITE_SYSTEM_MD").ok() {
+    if let Ok(write_system_md_var) = env::var("GEMINI_WRITE_SYSTEM_MD") {
         let write_system_md_var_lower = write_system_md_var.to_lowercase();
         if !["0", "false"].contains(&write_system_md_var_lower.as_str()) {
             let write_path = if ["1", "true"].contains(&write_system_md_var_lower.as_str()) {
@@ -272,7 +268,7 @@ fn get_default_system_prompt() -> String {
 }
 
 /// 检查是否为Git仓库
-fn is_git_repository(path: &PathBuf) -> bool {
+fn is_git_repository(path: &Path) -> bool {
     path.join(".git").exists()
 }
 
@@ -320,6 +316,9 @@ pub fn get_mcp_system_prompt(workspace_root: &str) -> String {
 - **钱包管理**：使用 `list_wallets`、`switch_wallet` 等工具
 - **网络信息**：使用 `get_network_info` 工具
 
+# 🧾 工具结果格式
+工具执行结果以JSON对象返回：成功时为 `{{"tool": 工具名, "success": true, "result": 结果}}`，失败时为 `{{"tool": 工具名, "success": false, "error": 错误信息}}`。`result` 是工具返回的结构化数据，直接读取其中的字段，不要猜测。
+
 # 工作目录
 当前工作目录: {}
 
@@ -564,7 +563,8 @@ mod tests {
     #[test]
     fn test_get_mcp_system_prompt() {
         let prompt = get_mcp_system_prompt("/test/workspace");
-        assert!(prompt.contains("你是一个智能文件操作助手"));
+        assert!(prompt.contains("你是Alou，一个专业的区块链支付助手"));
+        assert!(prompt.contains(r#"{"tool": 工具名, "success": true, "result": 结果}"#));
         assert!(prompt.contains("/test/workspace"));
     }
 
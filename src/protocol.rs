@@ -3,8 +3,9 @@ use std::fmt;
 
 use crate::error::{Error, ErrorCode};
 
-pub const LATEST_PROTOCOL_VERSION: &str = "2024-11-05";
-pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &[LATEST_PROTOCOL_VERSION, "2024-10-07"];
+pub const LATEST_PROTOCOL_VERSION: &str = "2025-06-18";
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] =
+    &[LATEST_PROTOCOL_VERSION, "2025-03-26", "2024-11-05", "2024-10-07"];
 pub const JSONRPC_VERSION: &str = "2.0";
 
 /// A unique identifier for a request
@@ -13,6 +14,9 @@ pub const JSONRPC_VERSION: &str = "2.0";
 pub enum RequestId {
     String(String),
     Number(i64),
+    /// `null`, only valid in an error response to a message whose id could not be
+    /// read, e.g. one that failed to parse
+    Null,
 }
 
 /// Base JSON-RPC request structure
@@ -126,6 +130,11 @@ impl From<Error> for ResponseError {
                 message: err.to_string(),
                 data: None,
             },
+            Error::Cancelled(msg) => ResponseError {
+                code: ErrorCode::RequestFailed.into(),
+                message: msg,
+                data: None,
+            },
             Error::Other(msg) => ResponseError {
                 code: ErrorCode::InternalError.into(),
                 message: msg,
@@ -140,6 +149,7 @@ impl fmt::Display for RequestId {
         match self {
             RequestId::String(s) => write!(f, "{}", s),
             RequestId::Number(n) => write!(f, "{}", n),
+            RequestId::Null => write!(f, "null"),
         }
     }
 }
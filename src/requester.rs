@@ -0,0 +1,359 @@
+//! Outgoing requests shared by both ends of a connection.
+//!
+//! The [`Client`](crate::client::Client) sends requests to the server and a
+//! server's [`Peer`](crate::server::Peer) sends requests to the client. Both keep
+//! the requests they are waiting on in a [`Requester`], which pairs each response
+//! read from the transport with the request that asked for it.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{oneshot, Mutex};
+use tokio::time::{timeout, Duration};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    error::{Error, ErrorCode},
+    protocol::{Notification, Request, RequestId, Response},
+    transport::{Message, Transport},
+};
+
+/// How long a request waits for its response unless told otherwise.
+pub(crate) const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Per-request settings for [`Client::request_with_options`](crate::client::Client::request_with_options)
+/// and [`Client::request_batch_with_options`](crate::client::Client::request_batch_with_options).
+///
+/// The default waits up to 120 seconds and cannot be cancelled.
+#[derive(Debug, Clone)]
+pub struct RequestOptions {
+    /// How long to wait for the response; `None` waits indefinitely.
+    pub timeout: Option<Duration>,
+    /// Token that aborts the request when cancelled.
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for RequestOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Some(REQUEST_TIMEOUT),
+            cancellation: None,
+        }
+    }
+}
+
+impl RequestOptions {
+    /// Sets how long to wait for the response.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Waits for the response without a time limit.
+    pub fn no_timeout(mut self) -> Self {
+        self.timeout = None;
+        self
+    }
+
+    /// Aborts the request once `token` is cancelled.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+}
+
+/// Requests awaiting a response, keyed by request id.
+type PendingRequests = Arc<Mutex<HashMap<RequestId, oneshot::Sender<Response>>>>;
+
+/// Sends requests over a transport and hands each response to the request waiting for it.
+///
+/// Whoever reads the transport passes every response it sees to [`Requester::resolve`].
+/// Cloning is cheap and yields a handle to the same set of pending requests.
+#[derive(Clone)]
+pub(crate) struct Requester {
+    transport: Arc<dyn Transport>,
+    pending: PendingRequests,
+    request_counter: Arc<AtomicI64>,
+    /// Set once no more responses will be read; later requests fail straight away.
+    closed: Arc<AtomicBool>,
+}
+
+impl Requester {
+    pub(crate) fn new(transport: Arc<dyn Transport>) -> Self {
+        Self {
+            transport,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            request_counter: Arc::new(AtomicI64::new(0)),
+            closed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Allocates the next request id.
+    fn next_request_id(&self) -> RequestId {
+        RequestId::Number(self.request_counter.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    /// Registers a waiter for the response to `id`, unless the connection is closed.
+    fn register(
+        &self,
+        pending: &mut HashMap<RequestId, oneshot::Sender<Response>>,
+        id: RequestId,
+    ) -> Result<oneshot::Receiver<Response>, Error> {
+        // Checked under the lock so a concurrent `close` cannot miss the new waiter.
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(Error::protocol(ErrorCode::InternalError, "Connection closed"));
+        }
+        let (tx, rx) = oneshot::channel();
+        pending.insert(id, tx);
+        Ok(rx)
+    }
+
+    /// Sends a request and waits for its result, honouring the timeout and
+    /// cancellation token in `options`. If the request times out, is cancelled or the
+    /// returned future is dropped, the other side is told to stop working on it with
+    /// `notifications/cancelled`.
+    pub(crate) async fn request(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        options: RequestOptions,
+    ) -> Result<serde_json::Value, Error> {
+        let id = self.next_request_id();
+        let request = Request::new(method, params, id.clone());
+        tracing::debug!(?request, "Sending request");
+
+        // Register before sending: some transports deliver the response during `send`.
+        let rx = self.register(&mut *self.pending.lock().await, id.clone())?;
+        let mut abandoned = Abandoned::new(self, method, vec![id]);
+
+        // Sending counts against the timeout too: over HTTP `send` only returns once
+        // the server has written the response body.
+        let exchange = async {
+            self.transport.send(Message::Request(request)).await?;
+            rx.await.map_err(|_| {
+                Error::protocol(
+                    ErrorCode::InternalError,
+                    "Connection closed while waiting for response",
+                )
+            })
+        };
+
+        match self.wait(method, &options, exchange, &mut abandoned).await? {
+            Ok(response) => {
+                abandoned.disarm();
+                response_result(response)
+            }
+            Err(e) => {
+                // The request may never have reached the other side, so there is
+                // nothing to cancel there.
+                abandoned.forget_quietly();
+                Err(e)
+            }
+        }
+    }
+
+    /// Sends several requests as a single JSON-RPC batch and waits for all of their
+    /// responses, returned in the same order as `requests`. `options` applies to the
+    /// batch as a whole.
+    pub(crate) async fn request_batch(
+        &self,
+        requests: Vec<(String, Option<serde_json::Value>)>,
+        options: RequestOptions,
+    ) -> Result<Vec<Result<serde_json::Value, Error>>, Error> {
+        let mut ids = Vec::with_capacity(requests.len());
+        let mut receivers = Vec::with_capacity(requests.len());
+        let mut messages = Vec::with_capacity(requests.len());
+        {
+            let mut pending = self.pending.lock().await;
+            for (method, params) in requests {
+                let id = self.next_request_id();
+                let rx = match self.register(&mut pending, id.clone()) {
+                    Ok(rx) => rx,
+                    Err(e) => {
+                        for id in ids {
+                            pending.remove(&id);
+                        }
+                        return Err(e);
+                    }
+                };
+                ids.push(id.clone());
+                receivers.push(rx);
+                messages.push(Message::Request(Request::new(method, params, id)));
+            }
+        }
+        tracing::debug!(?ids, "Sending request batch");
+        let mut abandoned = Abandoned::new(self, "batch", ids);
+
+        let exchange = async {
+            self.transport.send(Message::Batch(messages)).await?;
+            Ok::<_, Error>(futures::future::join_all(receivers).await)
+        };
+
+        match self.wait("batch", &options, exchange, &mut abandoned).await? {
+            Ok(responses) => {
+                abandoned.disarm();
+                Ok(responses
+                    .into_iter()
+                    .map(|response| match response {
+                        Ok(response) => response_result(response),
+                        Err(_) => Err(Error::protocol(
+                            ErrorCode::InternalError,
+                            "Connection closed while waiting for batch response",
+                        )),
+                    })
+                    .collect())
+            }
+            Err(e) => {
+                abandoned.forget_quietly();
+                Err(e)
+            }
+        }
+    }
+
+    /// Runs `exchange` within the timeout and cancellation token in `options`. Returns
+    /// the outcome of `exchange`, or the error to report if it timed out or was
+    /// cancelled; in that case `abandoned` is left armed to notify the other side.
+    async fn wait<T>(
+        &self,
+        method: &str,
+        options: &RequestOptions,
+        exchange: impl std::future::Future<Output = Result<T, Error>>,
+        abandoned: &mut Abandoned,
+    ) -> Result<Result<T, Error>, Error> {
+        let response = async {
+            match options.timeout {
+                Some(limit) => timeout(limit, exchange).await.ok(),
+                None => Some(exchange.await),
+            }
+        };
+        let cancelled = async {
+            match &options.cancellation {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            response = response => match response {
+                Some(outcome) => Ok(outcome),
+                None => {
+                    let secs = options.timeout.unwrap_or_default().as_secs_f64();
+                    tracing::error!("Request to '{}' timed out after {} seconds", method, secs);
+                    abandoned.reason = "timeout";
+                    Err(Error::Other(format!("Request to '{method}' timed out after {secs} seconds")))
+                }
+            },
+            _ = cancelled => {
+                tracing::debug!("Request to '{}' was cancelled", method);
+                Err(Error::Cancelled(format!("Request to '{method}' was cancelled")))
+            }
+        }
+    }
+
+    /// Hands a response read from the transport to the request waiting for it.
+    pub(crate) async fn resolve(&self, response: Response) {
+        match self.pending.lock().await.remove(&response.id) {
+            Some(tx) => {
+                tracing::debug!(?response, "Received matching response");
+                let _ = tx.send(response);
+            }
+            None => tracing::debug!(?response, "Received response for unknown request"),
+        }
+    }
+
+    /// Fails every request still waiting for a response; used once the transport closes.
+    pub(crate) async fn close(&self) {
+        let mut pending = self.pending.lock().await;
+        self.closed.store(true, Ordering::SeqCst);
+        // Dropping the senders wakes every waiting request with a closed-connection error.
+        pending.clear();
+    }
+}
+
+/// Cleans up after requests that are given up before their responses arrive.
+///
+/// Unless disarmed, dropping it forgets the waiters of `ids` and sends
+/// `notifications/cancelled` for each request that was still waiting. This also
+/// covers a caller that drops the request future, e.g. in `select!` or by aborting
+/// its task.
+struct Abandoned {
+    requester: Requester,
+    method: String,
+    ids: Vec<RequestId>,
+    reason: &'static str,
+    notify: bool,
+}
+
+impl Abandoned {
+    fn new(requester: &Requester, method: &str, ids: Vec<RequestId>) -> Self {
+        Self {
+            requester: requester.clone(),
+            method: method.to_string(),
+            ids,
+            reason: "cancelled by client",
+            // The spec forbids cancelling `initialize`.
+            notify: method != "initialize",
+        }
+    }
+
+    /// Keeps the waiters in place; every response has already been taken.
+    fn disarm(&mut self) {
+        self.ids.clear();
+    }
+
+    /// Forgets the waiters without notifying the other side.
+    fn forget_quietly(&mut self) {
+        self.notify = false;
+    }
+}
+
+impl Drop for Abandoned {
+    fn drop(&mut self) {
+        if self.ids.is_empty() {
+            return;
+        }
+        // Without a runtime there is no transport left to notify.
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let requester = self.requester.clone();
+        let ids = std::mem::take(&mut self.ids);
+        let (method, reason, notify) = (std::mem::take(&mut self.method), self.reason, self.notify);
+        // Don't make the caller wait on a transport that may be what is hanging.
+        runtime.spawn(async move {
+            let abandoned: Vec<RequestId> = {
+                let mut pending = requester.pending.lock().await;
+                ids.into_iter().filter(|id| pending.remove(id).is_some()).collect()
+            };
+            if !notify {
+                return;
+            }
+            for id in abandoned {
+                tracing::debug!(?id, "Cancelling request to '{}'", method);
+                let notification = Notification::new(
+                    "notifications/cancelled",
+                    Some(serde_json::json!({"requestId": id, "reason": reason})),
+                );
+                let sent = requester.transport.send(Message::Notification(notification)).await;
+                if let Err(e) = sent {
+                    tracing::warn!(?e, "Failed to notify peer of cancelled request");
+                }
+            }
+        });
+    }
+}
+
+/// Converts a response into its result, mapping a JSON-RPC error into `Error::Protocol`.
+fn response_result(response: Response) -> Result<serde_json::Value, Error> {
+    if let Some(error) = response.error {
+        tracing::error!(?error, "Peer returned error");
+        return Err(Error::Protocol {
+            code: error.code.into(),
+            message: error.message,
+            data: error.data,
+        });
+    }
+    response.result.ok_or_else(|| {
+        Error::protocol(ErrorCode::InternalError, "Response missing result")
+    })
+}
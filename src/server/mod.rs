@@ -1,15 +1,81 @@
 use async_trait::async_trait;
-use futures::StreamExt;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use futures::stream::Peekable;
+use futures::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{oneshot, OwnedSemaphorePermit, RwLock, Semaphore};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
     error::{Error, ErrorCode},
-    protocol::{Request, Response, ResponseError},
+    protocol::{Notification, Request, RequestId, Response, ResponseError},
     transport::{Message, Transport},
-    types::{ClientCapabilities, Implementation, ServerCapabilities},
+    types::{
+        ClientCapabilities, CompleteRequest, CompleteResult, Implementation, InitializeRequest,
+        InitializeResult, Progress, ProgressToken, ServerCapabilities,
+    },
 };
 
+mod peer;
+pub use peer::{Peer, RequestContext};
+
+mod router;
+pub use router::Router;
+
+/// Messages read from the transport, with a look at the next one.
+type Incoming = Peekable<Pin<Box<dyn Stream<Item = Result<Message, Error>> + Send>>>;
+
+/// Extracts the id of the request a `notifications/cancelled` notification refers to.
+fn cancelled_request_id(notification: &Notification) -> Option<RequestId> {
+    if notification.method != "notifications/cancelled" {
+        return None;
+    }
+    let request_id = notification.params.as_ref()?.get("requestId")?.clone();
+    serde_json::from_value(request_id).ok()
+}
+
+/// Extracts `_meta.progressToken` from request params.
+fn progress_token(params: Option<&serde_json::Value>) -> Option<ProgressToken> {
+    let token = params?.get("_meta")?.get("progressToken")?.clone();
+    serde_json::from_value(token).ok()
+}
+
+/// Sends `notifications/progress` for a request whose params carried a progress token.
+#[derive(Clone)]
+pub struct ProgressReporter {
+    peer: Peer,
+    token: ProgressToken,
+}
+
+impl ProgressReporter {
+    /// The token the client attached to the request.
+    pub fn token(&self) -> &ProgressToken {
+        &self.token
+    }
+
+    /// Reports that `progress` out of an optional `total` is done.
+    ///
+    /// `progress` must increase with every call, even if `total` is unknown.
+    pub async fn report(
+        &self,
+        progress: f64,
+        total: Option<f64>,
+        message: Option<String>,
+    ) -> Result<(), Error> {
+        let params = Progress {
+            progress_token: self.token.clone(),
+            progress,
+            total,
+            message,
+        };
+        self.peer
+            .notify("notifications/progress", Some(serde_json::to_value(params)?))
+            .await
+    }
+}
+
 /// Trait for implementing MCP server handlers
 #[async_trait]
 pub trait ServerHandler: Send + Sync {
@@ -23,120 +89,493 @@ pub trait ServerHandler: Send + Sync {
     /// Handle shutdown request
     async fn shutdown(&self) -> Result<(), Error>;
 
+    /// Name and version reported as `serverInfo` in the initialize result.
+    ///
+    /// Defaults to the name and version of this crate.
+    fn server_info(&self) -> Implementation {
+        Implementation {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            title: None,
+        }
+    }
+
+    /// Usage hints returned as `instructions` in the initialize result.
+    fn instructions(&self) -> Option<String> {
+        None
+    }
+
+    /// Handle `completion/complete`, suggesting values for a prompt argument or a
+    /// resource template variable.
+    ///
+    /// Servers implementing this should advertise the `completions` capability.
+    async fn complete(&self, _request: CompleteRequest) -> Result<CompleteResult, Error> {
+        Err(Error::protocol(
+            ErrorCode::MethodNotFound,
+            "Completion is not supported by this server",
+        ))
+    }
+
     /// Handle custom method calls
+    ///
+    /// If the client cancels the request, the returned future is dropped before completion.
     async fn handle_method(
         &self,
         method: &str,
         params: Option<serde_json::Value>,
     ) -> Result<serde_json::Value, Error>;
+
+    /// Handle custom method calls with a [`RequestContext`], which carries a [`Peer`]
+    /// for calling back into the client and a [`ProgressReporter`] if the client asked
+    /// for progress by setting `_meta.progressToken`.
+    ///
+    /// The default ignores `context` and calls [`ServerHandler::handle_method`].
+    async fn handle_method_with_context(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        _context: RequestContext,
+    ) -> Result<serde_json::Value, Error> {
+        self.handle_method(method, params).await
+    }
 }
 
+/// Where a [`Server`] is in the initialization handshake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lifecycle {
+    /// Waiting for the client's `initialize` request.
+    AwaitingInitialize,
+    /// The handler is processing `initialize`.
+    Initializing,
+    /// `initialize` was answered; waiting for `notifications/initialized`.
+    AwaitingInitialized,
+    /// The handshake is complete and all requests are accepted.
+    Ready,
+}
+
+/// How many requests [`Server`] handles at once unless configured otherwise.
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 64;
+
 /// Server state
+///
+/// Cloning is cheap and yields a handle to the same server, which is how
+/// request tasks share it.
+#[derive(Clone)]
 pub struct Server {
     transport: Arc<dyn Transport>,
     handler: Arc<dyn ServerHandler>,
-    initialized: Arc<RwLock<bool>>,
+    /// Sends notifications and requests to the client.
+    peer: Peer,
+    lifecycle: Arc<RwLock<Lifecycle>>,
+    /// The protocol version agreed with the client during initialize.
+    protocol_version: Arc<RwLock<Option<String>>>,
+    /// Bounds how many requests are handled at once.
+    limit: Arc<Semaphore>,
+    /// Requests being handled, so `notifications/cancelled` can abort them.
+    in_flight: Arc<Mutex<HashMap<RequestId, CancellationToken>>>,
+    /// Cancelled once an `exit` notification is received.
+    exit: CancellationToken,
 }
 
 impl Server {
     /// Create a new MCP server
     pub fn new(transport: Arc<dyn Transport>, handler: Arc<dyn ServerHandler>) -> Self {
         Self {
+            peer: Peer::new(transport.clone()),
             transport,
             handler,
-            initialized: Arc::new(RwLock::new(false)),
+            lifecycle: Arc::new(RwLock::new(Lifecycle::AwaitingInitialize)),
+            protocol_version: Arc::new(RwLock::new(None)),
+            limit: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_REQUESTS)),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            exit: CancellationToken::new(),
         }
     }
 
+    /// Returns the protocol version agreed with the client, once initialized.
+    pub async fn protocol_version(&self) -> Option<String> {
+        self.protocol_version.read().await.clone()
+    }
+
+    /// Returns a handle for sending notifications and requests to the client.
+    pub fn peer(&self) -> Peer {
+        self.peer.clone()
+    }
+
+    /// Sets how many requests are handled at once; further requests wait for a
+    /// free slot. Defaults to [`DEFAULT_MAX_CONCURRENT_REQUESTS`].
+    pub fn with_max_concurrent_requests(mut self, limit: usize) -> Self {
+        self.limit = Arc::new(Semaphore::new(limit.max(1)));
+        self
+    }
+
     /// Starts the server loop, receiving messages from the transport and processing them.
-    /// The loop ends either if the transport closes, or if an error occurs.
+    /// The loop ends either if the transport closes, an `exit` notification arrives, or
+    /// if an error occurs.
+    ///
+    /// Each request, including each request of a batch, is handled on its own task,
+    /// so a slow handler does not hold up other requests. Responses are sent as soon
+    /// as they are ready, carrying the id of the request they answer; a batch is
+    /// answered with one batch whose responses follow the order of its requests. A
+    /// `notifications/cancelled` drops the handler future of that request and no
+    /// response is sent.
+    ///
+    /// A request is only taken off the transport once a concurrency slot is free.
+    /// While waiting for one, responses and notifications from the client are still
+    /// handled, so handlers waiting on the client can finish and free their slot.
+    ///
+    /// Once the loop ends, requests that are still being handled are drained: the
+    /// server waits for them and sends their responses before returning.
+    /// Responses the client sends meanwhile are still routed to the handlers waiting
+    /// on them, until the transport closes.
     pub async fn start(&self) -> Result<(), Error> {
-        let mut stream = self.transport.receive();
-
-        while let Some(message) = stream.next().await {
-            match message? {
+        let mut stream = self.transport.receive().peekable();
+        let mut tasks = JoinSet::new();
+        // Whether the transport may still deliver messages, in particular responses
+        // to requests the handlers sent to the client.
+        let mut reading = true;
+
+        let result = loop {
+            let message = tokio::select! {
+                _ = self.exit.cancelled() => break Ok(()),
+                Some(_) = tasks.join_next(), if !tasks.is_empty() => continue,
+                message = stream.next() => match message {
+                    Some(message) => message,
+                    None => {
+                        reading = false;
+                        break Ok(());
+                    }
+                },
+            };
+            let message = match message {
+                Ok(message) => message,
+                Err(e) => {
+                    reading = false;
+                    break Err(e);
+                }
+            };
+            match message {
                 Message::Request(request) => {
-                    let response = match self.handle_request(request.clone()).await {
-                        Ok(response) => response,
-                        Err(err) => Response::error(request.id, ResponseError::from(err)),
-                    };
-                    self.transport.send(Message::Response(response)).await?;
+                    let permit = self.acquire_slot(&mut stream).await;
+                    self.spawn_request(&mut tasks, permit, request, None);
                 }
                 Message::Notification(notification) => {
-                    match notification.method.as_str() {
-                        "exit" => break,
-                        "initialized" => {
-                            *self.initialized.write().await = true;
+                    self.handle_notification(notification).await;
+                }
+                Message::Batch(messages) => {
+                    if messages.iter().any(|message| matches!(message, Message::Batch(_))) {
+                        break Err(Error::protocol(
+                            ErrorCode::InvalidRequest,
+                            "Server received nested batch",
+                        ));
+                    }
+                    let mut responses = Vec::new();
+                    for message in messages {
+                        match message {
+                            Message::Request(request) => {
+                                let permit = self.acquire_slot(&mut stream).await;
+                                let (tx, rx) = oneshot::channel();
+                                self.spawn_request(&mut tasks, permit, request, Some(tx));
+                                responses.push(rx);
+                            }
+                            Message::Notification(notification) => {
+                                self.handle_notification(notification).await;
+                            }
+                            Message::Response(response) => self.peer.resolve(response).await,
+                            // Answered in its place in the batch reply.
+                            Message::Invalid(response) => {
+                                let (tx, rx) = oneshot::channel();
+                                let _ = tx.send(response);
+                                responses.push(rx);
+                            }
+                            // Nested batches were rejected above.
+                            Message::Batch(_) => {}
                         }
-                        _ => {
-                            // Handle other notifications
+                    }
+                    if !responses.is_empty() {
+                        let server = self.clone();
+                        tasks.spawn(async move { server.reply_batch(responses).await });
+                    }
+                }
+                Message::Response(response) => self.peer.resolve(response).await,
+                Message::Invalid(response) => self.reply(Message::Response(response)).await,
+            }
+        };
+
+        // Let requests that are already running finish and send their responses. Their
+        // handlers may be waiting on the client, so keep routing its responses meanwhile.
+        if !reading {
+            self.peer.close().await;
+        }
+        while !tasks.is_empty() {
+            tokio::select! {
+                _ = tasks.join_next() => {}
+                message = stream.next(), if reading => match message {
+                    Some(Ok(Message::Response(response))) => self.peer.resolve(response).await,
+                    Some(Ok(Message::Batch(messages))) => {
+                        for message in messages {
+                            if let Message::Response(response) = message {
+                                self.peer.resolve(response).await;
+                            }
                         }
                     }
+                    Some(Ok(message)) => {
+                        tracing::debug!(?message, "Ignoring message received while shutting down");
+                    }
+                    Some(Err(_)) | None => {
+                        reading = false;
+                        self.peer.close().await;
+                    }
+                },
+            }
+        }
+        // No more responses will be read, so requests to the client cannot complete.
+        self.peer.close().await;
+        result
+    }
+
+    /// Registers a request as in flight and returns the token that cancels it.
+    fn track(&self, id: &RequestId) -> CancellationToken {
+        let token = CancellationToken::new();
+        self.in_flight
+            .lock()
+            .unwrap()
+            .insert(id.clone(), token.clone());
+        token
+    }
+
+    /// Sends a message to the client, logging failures since no one else can handle them.
+    async fn reply(&self, message: Message) {
+        if let Err(e) = self.transport.send(message).await {
+            tracing::warn!(?e, "Failed to send response");
+        }
+    }
+
+    /// Waits for a free concurrency slot. Meanwhile responses and notifications at
+    /// the front of `stream` are handled; a request stays queued on the transport.
+    async fn acquire_slot(&self, stream: &mut Incoming) -> OwnedSemaphorePermit {
+        let handled_inline = |message: &Result<Message, Error>| {
+            matches!(message, Ok(Message::Response(_) | Message::Notification(_)))
+        };
+        loop {
+            tokio::select! {
+                permit = self.limit.clone().acquire_owned() => {
+                    return permit.expect("the semaphore is never closed");
+                }
+                message = Pin::new(&mut *stream).next_if(handled_inline) => match message {
+                    Some(Ok(Message::Response(response))) => self.peer.resolve(response).await,
+                    Some(Ok(Message::Notification(notification))) => {
+                        self.handle_notification(notification).await;
+                    }
+                    // Something else is next, so there is nothing to do but wait.
+                    _ => {
+                        return self
+                            .limit
+                            .clone()
+                            .acquire_owned()
+                            .await
+                            .expect("the semaphore is never closed");
+                    }
+                },
+            }
+        }
+    }
+
+    /// Handles `request` on its own task while holding `permit`. The response goes to
+    /// `batch` if the request is part of one, and straight to the client otherwise.
+    fn spawn_request(
+        &self,
+        tasks: &mut JoinSet<()>,
+        permit: OwnedSemaphorePermit,
+        request: Request,
+        batch: Option<oneshot::Sender<Response>>,
+    ) {
+        let cancelled = self.track(&request.id);
+        let server = self.clone();
+        tasks.spawn(async move {
+            let response = server.run_request(request, cancelled).await;
+            drop(permit);
+            match (response, batch) {
+                (Some(response), Some(batch)) => {
+                    let _ = batch.send(response);
                 }
-                Message::Response(_) => {
-                    // Server shouldn't receive responses
-                    return Err(Error::protocol(
-                        ErrorCode::InvalidRequest,
-                        "Server received unexpected response",
-                    ));
+                (Some(response), None) => server.reply(Message::Response(response)).await,
+                (None, _) => {}
+            }
+        });
+    }
+
+    /// Answers a batch once all of its requests are done, skipping cancelled ones.
+    async fn reply_batch(&self, responses: Vec<oneshot::Receiver<Response>>) {
+        let responses: Vec<Message> = futures::future::join_all(responses)
+            .await
+            .into_iter()
+            .filter_map(Result::ok)
+            .map(Message::Response)
+            .collect();
+        if !responses.is_empty() {
+            self.reply(Message::Batch(responses)).await;
+        }
+    }
+
+    /// Handles a request unless it is cancelled first.
+    /// Returns `None` if the client cancelled the request.
+    async fn run_request(&self, request: Request, cancelled: CancellationToken) -> Option<Response> {
+        let id = request.id.clone();
+        let response = tokio::select! {
+            response = self.process_request(request, cancelled.clone()) => Some(response),
+            _ = cancelled.cancelled() => {
+                tracing::debug!(?id, "Request cancelled by client");
+                None
+            }
+        };
+        self.in_flight.lock().unwrap().remove(&id);
+        response
+    }
+
+    /// Handles a request, turning any error into an error response.
+    async fn process_request(&self, request: Request, cancellation: CancellationToken) -> Response {
+        let id = request.id.clone();
+        match self.handle_request(request, cancellation).await {
+            Ok(response) => response,
+            Err(err) => Response::error(id, ResponseError::from(err)),
+        }
+    }
+
+    /// Handles a notification; an `exit` notification stops the server loop.
+    async fn handle_notification(&self, notification: Notification) {
+        match notification.method.as_str() {
+            "exit" => self.exit.cancel(),
+            "notifications/cancelled" => {
+                let token = cancelled_request_id(&notification)
+                    .and_then(|id| self.in_flight.lock().unwrap().get(&id).cloned());
+                match token {
+                    Some(token) => token.cancel(),
+                    // The request already finished; nothing left to cancel.
+                    None => {
+                        tracing::debug!(?notification.params, "Ignoring cancellation of finished request")
+                    }
+                }
+            }
+            "notifications/initialized" => {
+                let mut lifecycle = self.lifecycle.write().await;
+                if *lifecycle == Lifecycle::AwaitingInitialized {
+                    *lifecycle = Lifecycle::Ready;
+                } else {
+                    tracing::warn!(?lifecycle, "Ignoring initialized notification outside the handshake");
                 }
             }
+            _ => {
+                // Handle other notifications
+            }
         }
+    }
 
-        Ok(())
+    /// Handles `initialize`, which is accepted only once.
+    async fn initialize(&self, request: Request) -> Result<Response, Error> {
+        {
+            let mut lifecycle = self.lifecycle.write().await;
+            if *lifecycle != Lifecycle::AwaitingInitialize {
+                return Err(Error::protocol(
+                    ErrorCode::InvalidRequest,
+                    "Server already initialized",
+                ));
+            }
+            *lifecycle = Lifecycle::Initializing;
+        }
+
+        let result = self.negotiate(request.params).await;
+        // A failed initialize may be retried.
+        *self.lifecycle.write().await = match result {
+            Ok(_) => Lifecycle::AwaitingInitialized,
+            Err(_) => Lifecycle::AwaitingInitialize,
+        };
+        Ok(Response::success(
+            request.id,
+            Some(serde_json::to_value(result?)?),
+        ))
     }
 
-    async fn handle_request(&self, request: Request) -> Result<Response, Error> {
-        let initialized = *self.initialized.read().await;
+    /// Agrees on a protocol version and asks the handler for its capabilities.
+    ///
+    /// The client's version is accepted if this crate supports it; otherwise the
+    /// server answers with the latest version it supports and the client decides
+    /// whether to continue.
+    async fn negotiate(&self, params: Option<serde_json::Value>) -> Result<InitializeResult, Error> {
+        let params: InitializeRequest = serde_json::from_value(params.unwrap_or_default())
+            .map_err(|e| Error::protocol(ErrorCode::InvalidParams, e.to_string()))?;
+        let protocol_version =
+            if crate::SUPPORTED_PROTOCOL_VERSIONS.contains(&params.protocol_version.as_str()) {
+                params.protocol_version
+            } else {
+                tracing::info!(requested = %params.protocol_version, "Client requested an unsupported protocol version");
+                crate::LATEST_PROTOCOL_VERSION.to_string()
+            };
+
+        self.peer
+            .set_client_capabilities(params.capabilities.clone())
+            .await;
+        let capabilities = self
+            .handler
+            .initialize(params.client_info, params.capabilities)
+            .await?;
+
+        *self.protocol_version.write().await = Some(protocol_version.clone());
+        self.transport.set_protocol_version(&protocol_version);
+        Ok(InitializeResult {
+            protocol_version,
+            server_info: self.handler.server_info(),
+            capabilities,
+            instructions: self.handler.instructions(),
+        })
+    }
 
+    async fn handle_request(
+        &self,
+        request: Request,
+        cancellation: CancellationToken,
+    ) -> Result<Response, Error> {
+        // `ping` is answered at any time, `initialize` only before the handshake.
         match request.method.as_str() {
-            "initialize" => {
-                if initialized {
-                    return Err(Error::protocol(
-                        ErrorCode::InvalidRequest,
-                        "Server already initialized",
-                    ));
-                }
+            "ping" => return Ok(Response::success(request.id, Some(serde_json::json!({})))),
+            "initialize" => return self.initialize(request).await,
+            _ => {}
+        }
 
-                let params: serde_json::Value = request.params.unwrap_or(serde_json::json!({}));
-                let implementation: Implementation = serde_json::from_value(
-                    params.get("implementation").cloned().unwrap_or_default(),
-                )?;
-                let capabilities: ClientCapabilities = serde_json::from_value(
-                    params.get("capabilities").cloned().unwrap_or_default(),
-                )?;
+        if *self.lifecycle.read().await != Lifecycle::Ready {
+            return Err(Error::protocol(
+                ErrorCode::ServerNotInitialized,
+                "Server not initialized",
+            ));
+        }
 
-                let result = self
-                    .handler
-                    .initialize(implementation, capabilities)
-                    .await?;
+        match request.method.as_str() {
+            "shutdown" => {
+                self.handler.shutdown().await?;
+                Ok(Response::success(request.id, None))
+            }
+            "completion/complete" => {
+                let params = request.params.unwrap_or_default();
+                let complete_request: CompleteRequest = serde_json::from_value(params)
+                    .map_err(|e| Error::protocol(ErrorCode::InvalidParams, e.to_string()))?;
+                let result = self.handler.complete(complete_request).await?;
                 Ok(Response::success(
                     request.id,
                     Some(serde_json::to_value(result)?),
                 ))
             }
-            "shutdown" => {
-                if !initialized {
-                    return Err(Error::protocol(
-                        ErrorCode::ServerNotInitialized,
-                        "Server not initialized",
-                    ));
-                }
-
-                self.handler.shutdown().await?;
-                Ok(Response::success(request.id, None))
-            }
             _ => {
-                if !initialized {
-                    return Err(Error::protocol(
-                        ErrorCode::ServerNotInitialized,
-                        "Server not initialized",
-                    ));
-                }
-
+                let context = RequestContext {
+                    peer: self.peer.clone(),
+                    progress: progress_token(request.params.as_ref()).map(|token| ProgressReporter {
+                        peer: self.peer.clone(),
+                        token,
+                    }),
+                    cancellation,
+                };
                 let result = self
                     .handler
-                    .handle_method(&request.method, request.params)
+                    .handle_method_with_context(&request.method, request.params, context)
                     .await?;
                 Ok(Response::success(request.id, Some(result)))
             }
@@ -148,11 +587,11 @@ impl Server {
 mod tests {
     use super::*;
     use async_trait::async_trait;
-    use futures::{Stream, StreamExt};
-    use std::{pin::Pin, time::Duration};
-    use tokio::sync::{broadcast, mpsc};
-    use crate::Notification;
+    use std::collections::HashSet;
+    use std::pin::Pin;
+    use std::time::Duration;
     use crate::protocol::RequestId;
+    use crate::transport::memory::duplex;
 
     struct TestHandler {
         init_delay: Duration,
@@ -196,58 +635,11 @@ mod tests {
         }
     }
 
-    struct MockTransport {
-        client_to_server: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<Result<Message, Error>>>>,
-        server_to_client: broadcast::Sender<Result<Message, Error>>,
-    }
-
-    impl MockTransport {
-        fn new() -> (
-            Self,
-            mpsc::UnboundedSender<Result<Message, Error>>,
-            broadcast::Receiver<Result<Message, Error>>,
-        ) {
-            let (tx1, rx1) = mpsc::unbounded_channel();
-            let (tx2, rx2) = broadcast::channel(100);
-            (
-                Self {
-                    client_to_server: Arc::new(tokio::sync::Mutex::new(rx1)),
-                    server_to_client: tx2.clone(),
-                },
-                tx1,
-                rx2,
-            )
-        }
-    }
-
-    #[async_trait]
-    impl Transport for MockTransport {
-        async fn send(&self, message: Message) -> Result<(), Error> {
-            self.server_to_client
-                .send(Ok(message))
-                .map(|_| ())
-                .map_err(|_| Error::protocol(ErrorCode::InternalError, "Failed to send message"))
-        }
-
-        fn receive(&self) -> Pin<Box<dyn Stream<Item = Result<Message, Error>> + Send>> {
-            let rx = self.client_to_server.clone();
-            Box::pin(async_stream::stream! {
-                let mut rx = rx.lock().await;
-                while let Some(msg) = rx.recv().await {
-                    yield msg;
-                }
-            })
-        }
-
-        async fn close(&self) -> Result<(), Error> {
-            Ok(())
-        }
-    }
-
     #[tokio::test]
     async fn test_server_initialization_timeout() {
         // Create transport and server with slow initialization
-        let (transport, client_tx, mut client_rx) = MockTransport::new();
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
         let handler = TestHandler::new(
             Duration::from_secs(6), // Initialization takes 6 seconds
             Duration::from_millis(100),
@@ -269,7 +661,7 @@ mod tests {
         let init_request = Request::new(
             "initialize",
             Some(serde_json::json!({
-                "implementation": {
+                "clientInfo": {
                     "name": "test-client",
                     "version": "0.1.0"
                 },
@@ -279,23 +671,40 @@ mod tests {
             RequestId::Number(1),
         );
 
-        let _ = client_tx.send(Ok(Message::Request(init_request)));
+        let _ = client.send(Message::Request(init_request)).await;
 
         // Try to receive response with 5 second timeout
-        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.recv()).await;
+        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await;
 
         // Should timeout
         assert!(result.is_err(), "Expected timeout error");
 
+        // A request sent meanwhile is rejected right away because the handshake
+        // has not completed.
+        let _ = client
+            .send(Message::Request(Request::new("test_method", None, RequestId::Number(2))))
+            .await;
+        match tokio::time::timeout(Duration::from_millis(500), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => {
+                assert_eq!(response.id, RequestId::Number(2));
+                assert_eq!(
+                    response.error.unwrap().code,
+                    i32::from(ErrorCode::ServerNotInitialized)
+                );
+            }
+            other => panic!("Expected error response, got {:?}", other),
+        }
+
         // Cleanup
-        let _ = client_tx.send(Ok(Message::Notification(Notification::new("exit", None))));
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
         let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
     }
 
     #[tokio::test]
     async fn test_server_fast_operation() {
         // Create transport and server with fast initialization
-        let (transport, client_tx, mut client_rx) = MockTransport::new();
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
         let handler = TestHandler::new(
             Duration::from_millis(100), // Fast initialization
             Duration::from_millis(100),
@@ -317,7 +726,7 @@ mod tests {
         let init_request = Request::new(
             "initialize",
             Some(serde_json::json!({
-                "implementation": {
+                "clientInfo": {
                     "name": "test-client",
                     "version": "0.1.0"
                 },
@@ -327,14 +736,14 @@ mod tests {
             RequestId::Number(1),
         );
 
-        let _ = client_tx.send(Ok(Message::Request(init_request)));
+        let _ = client.send(Message::Request(init_request)).await;
 
         // Try to receive response with 5 second timeout
-        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.recv()).await;
+        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await;
 
         // Should succeed
         assert!(result.is_ok(), "Operation should complete before timeout");
-        if let Ok(Ok(Ok(Message::Response(response)))) = result {
+        if let Ok(Some(Ok(Message::Response(response)))) = result {
             assert!(
                 response.error.is_none(),
                 "Response should not contain error"
@@ -345,10 +754,10 @@ mod tests {
         }
 
         // Send initialized notification
-        let _ = client_tx.send(Ok(Message::Notification(Notification::new(
-            "initialized",
+        let _ = client.send(Message::Notification(Notification::new(
+            "notifications/initialized",
             None,
-        ))));
+        ))).await;
 
         // Give server time to process notification
         tokio::time::sleep(Duration::from_millis(100)).await;
@@ -360,14 +769,14 @@ mod tests {
             RequestId::Number(2),
         );
 
-        let _ = client_tx.send(Ok(Message::Request(method_request)));
+        let _ = client.send(Message::Request(method_request)).await;
 
         // Try to receive response with 5 second timeout
-        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.recv()).await;
+        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await;
 
         // Should succeed
         assert!(result.is_ok(), "Operation should complete before timeout");
-        if let Ok(Ok(Ok(Message::Response(response)))) = result {
+        if let Ok(Some(Ok(Message::Response(response)))) = result {
             assert!(
                 response.error.is_none(),
                 "Response should not contain error"
@@ -382,14 +791,227 @@ mod tests {
         }
 
         // Cleanup
-        let _ = client_tx.send(Ok(Message::Notification(Notification::new("exit", None))));
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
+        let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
+    }
+
+    /// Sleeps for `slow_delay` on `slow_method` and answers every other method at once.
+    struct SlowMethodHandler {
+        slow_delay: Duration,
+    }
+
+    #[async_trait]
+    impl ServerHandler for SlowMethodHandler {
+        async fn initialize(
+            &self,
+            _implementation: Implementation,
+            _capabilities: ClientCapabilities,
+        ) -> Result<ServerCapabilities, Error> {
+            Ok(ServerCapabilities::default())
+        }
+
+        async fn shutdown(&self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn handle_method(
+            &self,
+            method: &str,
+            _params: Option<serde_json::Value>,
+        ) -> Result<serde_json::Value, Error> {
+            if method == "slow_method" {
+                tokio::time::sleep(self.slow_delay).await;
+            }
+            Ok(serde_json::json!({"method": method}))
+        }
+    }
+
+    /// Starts `server`, completes the handshake and returns the server task.
+    async fn start_initialized(
+        server: Server,
+        client: &crate::transport::memory::MemoryTransport,
+        client_rx: &mut Pin<Box<dyn futures::Stream<Item = Result<Message, Error>> + Send>>,
+    ) -> tokio::task::JoinHandle<Result<(), Error>> {
+        let server_handle = tokio::spawn(async move { server.start().await });
+        let init_request = Request::new(
+            "initialize",
+            Some(serde_json::json!({
+                "clientInfo": {"name": "test-client", "version": "0.1.0"},
+                "capabilities": {},
+                "protocolVersion": "2025-06-18"
+            })),
+            RequestId::Number(1),
+        );
+        let _ = client.send(Message::Request(init_request)).await;
+        let _ = tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await;
+        let _ = client
+            .send(Message::Notification(Notification::new("notifications/initialized", None)))
+            .await;
+        server_handle
+    }
+
+    /// Receives the next response and returns its id.
+    async fn next_response_id(
+        client_rx: &mut Pin<Box<dyn futures::Stream<Item = Result<Message, Error>> + Send>>,
+    ) -> RequestId {
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => {
+                assert!(response.error.is_none(), "Response should not contain error");
+                response.id
+            }
+            other => panic!("Expected response, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_request_does_not_delay_other_responses() {
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
+        let handler = SlowMethodHandler { slow_delay: Duration::from_secs(2) };
+        let server = Server::new(Arc::new(transport), Arc::new(handler));
+        let server_handle = start_initialized(server, &client, &mut client_rx).await;
+
+        let started = std::time::Instant::now();
+        for (method, id) in [("slow_method", 2), ("fast_method", 3), ("ping", 4)] {
+            let _ = client
+                .send(Message::Request(Request::new(method, None, RequestId::Number(id))))
+                .await;
+        }
+
+        // The fast request and the ping are answered while the slow handler still runs.
+        let answered: HashSet<_> = [
+            next_response_id(&mut client_rx).await,
+            next_response_id(&mut client_rx).await,
+        ]
+        .into();
+        assert_eq!(answered, [3, 4].map(RequestId::Number).into());
+        assert!(started.elapsed() < Duration::from_secs(1), "took {:?}", started.elapsed());
+
+        assert_eq!(next_response_id(&mut client_rx).await, RequestId::Number(2));
+        assert!(started.elapsed() >= Duration::from_secs(2));
+
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
+        let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_frees_slot_for_queued_request() {
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
+        let handler = SlowMethodHandler { slow_delay: Duration::from_secs(5) };
+        let server = Server::new(Arc::new(transport), Arc::new(handler)).with_max_concurrent_requests(1);
+        let server_handle = start_initialized(server, &client, &mut client_rx).await;
+
+        // The fast request waits for the only slot, which the slow request holds
+        // until the client cancels it.
+        for (method, id) in [("slow_method", 2), ("fast_method", 3)] {
+            let _ = client
+                .send(Message::Request(Request::new(method, None, RequestId::Number(id))))
+                .await;
+        }
+        let _ = client
+            .send(Message::Notification(Notification::new(
+                "notifications/cancelled",
+                Some(serde_json::json!({"requestId": 2})),
+            )))
+            .await;
+
+        match tokio::time::timeout(Duration::from_secs(1), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => {
+                assert_eq!(response.id, RequestId::Number(3));
+            }
+            other => panic!("Expected response to the queued request, got {:?}", other),
+        }
+
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
+        let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit() {
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
+        let handler = SlowMethodHandler { slow_delay: Duration::from_millis(300) };
+        let server = Server::new(Arc::new(transport), Arc::new(handler)).with_max_concurrent_requests(1);
+        let server_handle = start_initialized(server, &client, &mut client_rx).await;
+
+        let started = std::time::Instant::now();
+        for id in [2, 3] {
+            let _ = client
+                .send(Message::Request(Request::new("slow_method", None, RequestId::Number(id))))
+                .await;
+        }
+
+        // With a single slot the second slow request only starts after the first.
+        assert_eq!(next_response_id(&mut client_rx).await, RequestId::Number(2));
+        assert_eq!(next_response_id(&mut client_rx).await, RequestId::Number(3));
+        assert!(started.elapsed() >= Duration::from_millis(600));
+
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
+        let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
+    }
+
+    #[tokio::test]
+    async fn test_batch_requests_share_concurrency_limit() {
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
+        let handler = SlowMethodHandler { slow_delay: Duration::from_millis(300) };
+        let server = Server::new(Arc::new(transport), Arc::new(handler)).with_max_concurrent_requests(2);
+        let server_handle = start_initialized(server, &client, &mut client_rx).await;
+
+        let started = std::time::Instant::now();
+        let batch = [2, 3, 4]
+            .into_iter()
+            .map(|id| Message::Request(Request::new("slow_method", None, RequestId::Number(id))))
+            .collect();
+        let _ = client.send(Message::Batch(batch)).await;
+
+        // Two requests of the batch run at once and the third waits for a free slot.
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Batch(responses)))) => {
+                let ids: Vec<_> = responses
+                    .into_iter()
+                    .map(|message| match message {
+                        Message::Response(response) => response.id,
+                        other => panic!("Expected response, got {:?}", other),
+                    })
+                    .collect();
+                assert_eq!(ids, [2, 3, 4].map(RequestId::Number));
+            }
+            other => panic!("Expected batch response, got {:?}", other),
+        }
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(600), "took {:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(850), "took {:?}", elapsed);
+
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
         let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
     }
 
+    #[tokio::test]
+    async fn test_exit_drains_in_flight_requests() {
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
+        let handler = SlowMethodHandler { slow_delay: Duration::from_millis(300) };
+        let server = Server::new(Arc::new(transport), Arc::new(handler));
+        let server_handle = start_initialized(server, &client, &mut client_rx).await;
+
+        let _ = client
+            .send(Message::Request(Request::new("slow_method", None, RequestId::Number(2))))
+            .await;
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
+
+        // The request that was running when `exit` arrived is still answered.
+        assert_eq!(next_response_id(&mut client_rx).await, RequestId::Number(2));
+        let result = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
+        assert!(matches!(result, Ok(Ok(Ok(())))), "Server should stop after draining");
+    }
+
     #[tokio::test]
     async fn test_server_error_handling() {
         // Create transport and server
-        let (transport, client_tx, mut client_rx) = MockTransport::new();
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
         let handler = TestHandler::new(
             Duration::from_millis(100),
             Duration::from_millis(100),
@@ -414,13 +1036,13 @@ mod tests {
             RequestId::Number(1),
         );
 
-        let _ = client_tx.send(Ok(Message::Request(method_request)));
+        let _ = client.send(Message::Request(method_request)).await;
 
         // Should receive error response
-        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.recv()).await;
+        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await;
 
         assert!(result.is_ok(), "Should receive error response");
-        if let Ok(Ok(Ok(Message::Response(response)))) = result {
+        if let Ok(Some(Ok(Message::Response(response)))) = result {
             assert!(response.error.is_some(), "Response should contain error");
             assert_eq!(
                 response.error.as_ref().unwrap().code,
@@ -432,14 +1054,15 @@ mod tests {
         }
 
         // Cleanup
-        let _ = client_tx.send(Ok(Message::Notification(Notification::new("exit", None))));
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
         let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
     }
 
     #[tokio::test]
     async fn test_double_initialization() {
         // Create transport and server
-        let (transport, client_tx, mut client_rx) = MockTransport::new();
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
         let handler = TestHandler::new(
             Duration::from_millis(100),
             Duration::from_millis(100),
@@ -461,7 +1084,7 @@ mod tests {
         let init_request = Request::new(
             "initialize",
             Some(serde_json::json!({
-                "implementation": {
+                "clientInfo": {
                     "name": "test-client",
                     "version": "0.1.0"
                 },
@@ -471,16 +1094,16 @@ mod tests {
             RequestId::Number(1),
         );
 
-        let _ = client_tx.send(Ok(Message::Request(init_request.clone())));
+        let _ = client.send(Message::Request(init_request.clone())).await;
 
         // Wait for first response
-        let _ = client_rx.recv().await;
+        let _ = client_rx.next().await;
 
         // Send initialized notification
-        let _ = client_tx.send(Ok(Message::Notification(Notification::new(
-            "initialized",
+        let _ = client.send(Message::Notification(Notification::new(
+            "notifications/initialized",
             None,
-        ))));
+        ))).await;
 
         // Give server time to process notification
         tokio::time::sleep(Duration::from_millis(100)).await;
@@ -489,7 +1112,7 @@ mod tests {
         let init_request2 = Request::new(
             "initialize",
             Some(serde_json::json!({
-                "implementation": {
+                "clientInfo": {
                     "name": "test-client",
                     "version": "0.1.0"
                 },
@@ -499,14 +1122,14 @@ mod tests {
             RequestId::Number(2),
         );
 
-        let _ = client_tx.send(Ok(Message::Request(init_request2)));
+        let _ = client.send(Message::Request(init_request2)).await;
 
         // Try to receive response
-        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.recv()).await;
+        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await;
 
         // Should receive error response
         assert!(result.is_ok(), "Should receive response");
-        if let Ok(Ok(Ok(Message::Response(response)))) = result {
+        if let Ok(Some(Ok(Message::Response(response)))) = result {
             assert!(response.error.is_some(), "Response should contain error");
             assert_eq!(
                 response.error.as_ref().unwrap().code,
@@ -518,14 +1141,15 @@ mod tests {
         }
 
         // Cleanup
-        let _ = client_tx.send(Ok(Message::Notification(Notification::new("exit", None))));
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
         let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
     }
 
     #[tokio::test]
     async fn test_shutdown_handling() {
         // Create transport and server
-        let (transport, client_tx, mut client_rx) = MockTransport::new();
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
         let handler = TestHandler::new(
             Duration::from_millis(100),
             Duration::from_millis(100),
@@ -547,7 +1171,7 @@ mod tests {
         let init_request = Request::new(
             "initialize",
             Some(serde_json::json!({
-                "implementation": {
+                "clientInfo": {
                     "name": "test-client",
                     "version": "0.1.0"
                 },
@@ -557,16 +1181,16 @@ mod tests {
             RequestId::Number(1),
         );
 
-        let _ = client_tx.send(Ok(Message::Request(init_request)));
+        let _ = client.send(Message::Request(init_request)).await;
 
         // Wait for response
-        let _ = client_rx.recv().await;
+        let _ = client_rx.next().await;
 
         // Send initialized notification
-        let _ = client_tx.send(Ok(Message::Notification(Notification::new(
-            "initialized",
+        let _ = client.send(Message::Notification(Notification::new(
+            "notifications/initialized",
             None,
-        ))));
+        ))).await;
 
         // Give server time to process notification
         tokio::time::sleep(Duration::from_millis(100)).await;
@@ -574,14 +1198,14 @@ mod tests {
         // Send shutdown request
         let shutdown_request = Request::new("shutdown", None, RequestId::Number(2));
 
-        let _ = client_tx.send(Ok(Message::Request(shutdown_request)));
+        let _ = client.send(Message::Request(shutdown_request)).await;
 
         // Try to receive response
-        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.recv()).await;
+        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await;
 
         // Should receive success response
         assert!(result.is_ok(), "Should receive response");
-        if let Ok(Ok(Ok(Message::Response(response)))) = result {
+        if let Ok(Some(Ok(Message::Response(response)))) = result {
             assert!(
                 response.error.is_none(),
                 "Response should not contain error"
@@ -595,7 +1219,7 @@ mod tests {
         }
 
         // Send exit notification
-        let _ = client_tx.send(Ok(Message::Notification(Notification::new("exit", None))));
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
 
         // Server should exit
         let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
@@ -604,7 +1228,8 @@ mod tests {
     #[tokio::test]
     async fn test_invalid_message_handling() {
         // Create transport and server
-        let (transport, client_tx, mut client_rx) = MockTransport::new();
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
         let handler = TestHandler::new(
             Duration::from_millis(100),
             Duration::from_millis(100),
@@ -622,18 +1247,651 @@ mod tests {
         // Give server time to start
         tokio::time::sleep(Duration::from_millis(100)).await;
 
-        // Send a response message (which server should not receive)
+        // Send a response to a request the server never made
         let response = Response::success(RequestId::Number(1), None);
-        let _ = client_tx.send(Ok(Message::Response(response)));
-
-        // Give server time to process
-        tokio::time::sleep(Duration::from_millis(100)).await;
+        let _ = client.send(Message::Response(response)).await;
+
+        // The response is ignored and the server keeps serving
+        let _ = client
+            .send(Message::Request(Request::new("ping", None, RequestId::Number(2))))
+            .await;
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => assert_eq!(response.id, RequestId::Number(2)),
+            other => panic!("Expected ping response, got {:?}", other),
+        }
 
-        // Server should have exited with error
+        // Server should exit
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
         let result = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
         assert!(result.is_ok(), "Server should exit");
+    }
+
+    #[tokio::test]
+    async fn test_batch_request_handling() {
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
+        let handler = TestHandler::new(
+            Duration::from_millis(10),
+            Duration::from_millis(10),
+            Duration::from_millis(10),
+        );
+        let server = Server::new(Arc::new(transport), Arc::new(handler));
+
+        let server_handle = tokio::spawn(async move {
+            if let Err(e) = server.start().await {
+                tracing::warn!("Server error: {}", e);
+            }
+        });
+
+        // Requests within a batch run concurrently, so initialize on its own.
+        let batch = Message::Batch(vec![Message::Request(Request::new(
+            "initialize",
+            Some(serde_json::json!({
+                "clientInfo": {"name": "test-client", "version": "0.1.0"},
+                "capabilities": {},
+                "protocolVersion": "2024-11-05"
+            })),
+            RequestId::Number(1),
+        ))]);
+        let _ = client.send(batch).await;
+        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await;
+        assert!(
+            matches!(&result, Ok(Some(Ok(Message::Batch(responses)))) if responses.len() == 1),
+            "Expected batch response, got {:?}",
+            result
+        );
+
+        // Notifications in a batch are handled before the requests after them start.
+        let batch = Message::Batch(vec![
+            Message::Notification(Notification::new("notifications/initialized", None)),
+            Message::Request(Request::new("test_method", None, RequestId::Number(2))),
+            Message::Request(Request::new("test_method", None, RequestId::Number(3))),
+        ]);
+        let _ = client.send(batch).await;
+
+        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await;
+        if let Ok(Some(Ok(Message::Batch(responses)))) = result {
+            assert_eq!(responses.len(), 2, "Only requests should be answered");
+            for (message, id) in responses.iter().zip([2, 3]) {
+                match message {
+                    Message::Response(response) => {
+                        assert_eq!(response.id, RequestId::Number(id));
+                        assert!(response.error.is_none(), "Response should not contain error");
+                    }
+                    other => panic!("Expected response, got {:?}", other),
+                }
+            }
+        } else {
+            panic!("Expected batch response, got {:?}", result);
+        }
+
+        // A malformed element is answered with its own error, in its place.
+        let batch: Message = serde_json::from_str(
+            r#"[{"jsonrpc":"2.0","id":4,"method":"test_method"},{"jsonrpc":"2.0","id":5}]"#,
+        )
+        .unwrap();
+        let _ = client.send(batch).await;
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Batch(responses)))) => {
+                let answers: Vec<_> = responses
+                    .iter()
+                    .map(|message| match message {
+                        Message::Response(response) => {
+                            (response.id.clone(), response.error.as_ref().map(|e| e.code))
+                        }
+                        other => panic!("Expected response, got {:?}", other),
+                    })
+                    .collect();
+                assert_eq!(
+                    answers,
+                    [
+                        (RequestId::Number(4), None),
+                        (RequestId::Number(5), Some(i32::from(ErrorCode::InvalidRequest))),
+                    ]
+                );
+            }
+            other => panic!("Expected batch response, got {:?}", other),
+        }
 
         // Cleanup
-        let _ = client_tx.send(Ok(Message::Notification(Notification::new("exit", None))));
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
+        let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_request_is_not_answered() {
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
+        let handler = TestHandler::new(
+            Duration::from_millis(10),
+            Duration::from_millis(10),
+            Duration::from_secs(30), // Methods would run far longer than the test
+        );
+        let server = Server::new(Arc::new(transport), Arc::new(handler));
+
+        let server_handle = tokio::spawn(async move {
+            if let Err(e) = server.start().await {
+                tracing::warn!("Server error: {}", e);
+            }
+        });
+
+        let init_request = Request::new(
+            "initialize",
+            Some(serde_json::json!({
+                "clientInfo": {"name": "test-client", "version": "0.1.0"},
+                "capabilities": {},
+                "protocolVersion": "2024-11-05"
+            })),
+            RequestId::Number(1),
+        );
+        let _ = client.send(Message::Request(init_request)).await;
+        let _ = tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await;
+        let _ = client
+            .send(Message::Notification(Notification::new("notifications/initialized", None)))
+            .await;
+
+        // Start a slow method, cancel it, then ask for something quick.
+        let _ = client
+            .send(Message::Request(Request::new("slow_method", None, RequestId::Number(2))))
+            .await;
+        let _ = client
+            .send(Message::Notification(Notification::new(
+                "notifications/cancelled",
+                Some(serde_json::json!({"requestId": 2, "reason": "test"})),
+            )))
+            .await;
+        let _ = client
+            .send(Message::Request(Request::new("shutdown", None, RequestId::Number(3))))
+            .await;
+
+        // The first response must be for the shutdown request; the cancelled one is never answered.
+        let result = tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await;
+        match result {
+            Ok(Some(Ok(Message::Response(response)))) => {
+                assert_eq!(response.id, RequestId::Number(3));
+            }
+            other => panic!("Expected shutdown response, got {:?}", other),
+        }
+
+        // Cleanup
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
+        let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
+    }
+
+    /// Completes network names for the `network` argument of any prompt.
+    struct CompletionHandler;
+
+    #[async_trait]
+    impl ServerHandler for CompletionHandler {
+        async fn initialize(
+            &self,
+            _implementation: Implementation,
+            _capabilities: ClientCapabilities,
+        ) -> Result<ServerCapabilities, Error> {
+            Ok(ServerCapabilities {
+                completions: Some(Default::default()),
+                ..Default::default()
+            })
+        }
+
+        async fn shutdown(&self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn complete(&self, request: CompleteRequest) -> Result<CompleteResult, Error> {
+            assert_eq!(request.argument.name, "network");
+            let values: Vec<String> = ["mainnet", "testnet", "devnet"]
+                .into_iter()
+                .filter(|network| network.starts_with(&request.argument.value))
+                .map(str::to_string)
+                .collect();
+            Ok(CompleteResult {
+                completion: crate::types::Completion {
+                    total: Some(values.len() as u64),
+                    values,
+                    has_more: Some(false),
+                },
+            })
+        }
+
+        async fn handle_method(
+            &self,
+            method: &str,
+            _params: Option<serde_json::Value>,
+        ) -> Result<serde_json::Value, Error> {
+            Err(Error::protocol(ErrorCode::MethodNotFound, method))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_completion_request() {
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
+        let server = Server::new(Arc::new(transport), Arc::new(CompletionHandler));
+        let server_handle = tokio::spawn(async move { server.start().await });
+
+        let init_request = Request::new(
+            "initialize",
+            Some(serde_json::json!({
+                "clientInfo": {"name": "test-client", "version": "0.1.0"},
+                "capabilities": {},
+                "protocolVersion": "2025-06-18"
+            })),
+            RequestId::Number(1),
+        );
+        let _ = client.send(Message::Request(init_request)).await;
+        let _ = tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await;
+        let _ = client
+            .send(Message::Notification(Notification::new("notifications/initialized", None)))
+            .await;
+
+        let complete_request = Request::new(
+            "completion/complete",
+            Some(serde_json::json!({
+                "ref": {"type": "ref/prompt", "name": "pay"},
+                "argument": {"name": "network", "value": "t"}
+            })),
+            RequestId::Number(2),
+        );
+        let _ = client.send(Message::Request(complete_request)).await;
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => {
+                assert_eq!(
+                    response.result,
+                    Some(serde_json::json!({
+                        "completion": {"values": ["testnet"], "total": 1, "hasMore": false}
+                    }))
+                );
+            }
+            other => panic!("Expected completion response, got {:?}", other),
+        }
+
+        // A malformed reference is rejected as invalid params.
+        let invalid_request = Request::new(
+            "completion/complete",
+            Some(serde_json::json!({"ref": {"type": "ref/unknown"}, "argument": {"name": "network", "value": ""}})),
+            RequestId::Number(3),
+        );
+        let _ = client.send(Message::Request(invalid_request)).await;
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => {
+                assert_eq!(response.error.unwrap().code, i32::from(ErrorCode::InvalidParams));
+            }
+            other => panic!("Expected error response, got {:?}", other),
+        }
+
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
+        let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
+    }
+
+    /// Reports halfway progress before answering, if the client asked for progress.
+    struct ProgressHandler;
+
+    #[async_trait]
+    impl ServerHandler for ProgressHandler {
+        async fn initialize(
+            &self,
+            _implementation: Implementation,
+            _capabilities: ClientCapabilities,
+        ) -> Result<ServerCapabilities, Error> {
+            Ok(ServerCapabilities::default())
+        }
+
+        async fn shutdown(&self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn handle_method(
+            &self,
+            _method: &str,
+            _params: Option<serde_json::Value>,
+        ) -> Result<serde_json::Value, Error> {
+            Ok(serde_json::json!({"status": "ok"}))
+        }
+
+        async fn handle_method_with_context(
+            &self,
+            method: &str,
+            params: Option<serde_json::Value>,
+            context: RequestContext,
+        ) -> Result<serde_json::Value, Error> {
+            if let Some(progress) = context.progress {
+                progress.report(1.0, Some(2.0), Some("halfway".to_string())).await?;
+            }
+            self.handle_method(method, params).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_progress_notifications() {
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
+        let server = Server::new(Arc::new(transport), Arc::new(ProgressHandler));
+        let server_handle = tokio::spawn(async move { server.start().await });
+
+        let init_request = Request::new(
+            "initialize",
+            Some(serde_json::json!({
+                "clientInfo": {"name": "test-client", "version": "0.1.0"},
+                "capabilities": {},
+                "protocolVersion": "2025-06-18"
+            })),
+            RequestId::Number(1),
+        );
+        let _ = client.send(Message::Request(init_request)).await;
+        let _ = tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await;
+        let _ = client
+            .send(Message::Notification(Notification::new("notifications/initialized", None)))
+            .await;
+
+        let request = Request::new(
+            "tools/call",
+            Some(serde_json::json!({
+                "name": "transfer",
+                "arguments": {},
+                "_meta": {"progressToken": "transfer-1"}
+            })),
+            RequestId::Number(2),
+        );
+        let _ = client.send(Message::Request(request)).await;
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Notification(notification)))) => {
+                assert_eq!(notification.method, "notifications/progress");
+                assert_eq!(
+                    notification.params,
+                    Some(serde_json::json!({
+                        "progressToken": "transfer-1",
+                        "progress": 1.0,
+                        "total": 2.0,
+                        "message": "halfway"
+                    }))
+                );
+            }
+            other => panic!("Expected progress notification, got {:?}", other),
+        }
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => {
+                assert_eq!(response.id, RequestId::Number(2));
+                assert!(response.error.is_none());
+            }
+            other => panic!("Expected response, got {:?}", other),
+        }
+
+        // Without a progress token the handler gets no reporter and sends no progress.
+        let request = Request::new("tools/call", Some(serde_json::json!({"name": "transfer"})), RequestId::Number(3));
+        let _ = client.send(Message::Request(request)).await;
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => assert_eq!(response.id, RequestId::Number(3)),
+            other => panic!("Expected response, got {:?}", other),
+        }
+
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
+        let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
+    }
+
+    #[tokio::test]
+    async fn test_initialize_handshake() {
+        let (transport, client) = duplex();
+        let mut client_rx = client.receive();
+        let handler = TestHandler::new(Duration::ZERO, Duration::ZERO, Duration::ZERO);
+        let server = Server::new(Arc::new(transport), Arc::new(handler));
+        let server_handle = tokio::spawn({
+            let server = server.clone();
+            async move { server.start().await }
+        });
+
+        // Ping is answered before initialization.
+        let _ = client.send(Message::Request(Request::new("ping", None, RequestId::Number(1)))).await;
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => {
+                assert_eq!(response.result, Some(serde_json::json!({})));
+            }
+            other => panic!("Expected ping response, got {:?}", other),
+        }
+
+        // An unknown version is answered with the latest supported one.
+        let init_request = Request::new(
+            "initialize",
+            Some(serde_json::json!({
+                "clientInfo": {"name": "test-client", "version": "0.1.0"},
+                "capabilities": {},
+                "protocolVersion": "1999-01-01"
+            })),
+            RequestId::Number(2),
+        );
+        let _ = client.send(Message::Request(init_request)).await;
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => {
+                let result: InitializeResult = serde_json::from_value(response.result.unwrap()).unwrap();
+                assert_eq!(result.protocol_version, crate::LATEST_PROTOCOL_VERSION);
+                assert_eq!(result.server_info.name, env!("CARGO_PKG_NAME"));
+            }
+            other => panic!("Expected initialize response, got {:?}", other),
+        }
+
+        // Requests are rejected until the client sends `notifications/initialized`.
+        let request = Request::new("test_method", None, RequestId::Number(3));
+        let _ = client.send(Message::Request(request)).await;
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => {
+                assert_eq!(response.error.unwrap().code, ErrorCode::ServerNotInitialized as i32);
+            }
+            other => panic!("Expected error response, got {:?}", other),
+        }
+
+        let _ = client
+            .send(Message::Notification(Notification::new("notifications/initialized", None)))
+            .await;
+        let request = Request::new("test_method", None, RequestId::Number(4));
+        let _ = client.send(Message::Request(request)).await;
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => assert!(response.error.is_none()),
+            other => panic!("Expected response, got {:?}", other),
+        }
+        assert_eq!(
+            server.protocol_version().await.as_deref(),
+            Some(crate::LATEST_PROTOCOL_VERSION)
+        );
+
+        let _ = client.send(Message::Notification(Notification::new("exit", None))).await;
+        let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
+    }
+
+    #[tokio::test]
+    async fn test_client_server_handshake() {
+        let (server_transport, client_transport) = duplex();
+        let router = Router::default().tool("echo", "Echo the text back", |args: EchoArgs| async move {
+            Ok(crate::types::CallToolResult {
+                content: vec![crate::types::MessageContent::text(args.text)],
+                structured_content: None,
+                is_error: false,
+            })
+        });
+        let server = Server::new(Arc::new(server_transport), Arc::new(router));
+        let server_handle = tokio::spawn(async move { server.start().await });
+
+        let client = crate::client::Client::new(Arc::new(client_transport), None);
+        let result = client
+            .initialize(
+                Implementation {
+                    name: "test-client".to_string(),
+                    version: "0.1.0".to_string(),
+                    title: None,
+                },
+                ClientCapabilities::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.protocol_version, crate::LATEST_PROTOCOL_VERSION);
+        assert!(result.capabilities.tools.is_some());
+
+        client.request("ping", None).await.unwrap();
+        let tool_result = client
+            .call_tool("echo", serde_json::json!({"text": "hello"}))
+            .await
+            .unwrap();
+        assert_eq!(tool_result.text(), "hello");
+
+        let _ = client.notify("exit", None).await;
+        let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
+    }
+
+    #[derive(serde::Deserialize, schemars::JsonSchema)]
+    struct EchoArgs {
+        text: String,
+    }
+
+    /// Calls back into the client while handling `ask`.
+    /// Calls back into the client, pausing for `delay` after announcing itself.
+    struct PeerHandler {
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl ServerHandler for PeerHandler {
+        async fn initialize(
+            &self,
+            _implementation: Implementation,
+            _capabilities: ClientCapabilities,
+        ) -> Result<ServerCapabilities, Error> {
+            Ok(ServerCapabilities::default())
+        }
+
+        async fn shutdown(&self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        async fn handle_method(
+            &self,
+            method: &str,
+            _params: Option<serde_json::Value>,
+        ) -> Result<serde_json::Value, Error> {
+            Err(Error::protocol(ErrorCode::MethodNotFound, method.to_string()))
+        }
+
+        async fn handle_method_with_context(
+            &self,
+            _method: &str,
+            _params: Option<serde_json::Value>,
+            context: RequestContext,
+        ) -> Result<serde_json::Value, Error> {
+            let peer = context.peer;
+            assert!(peer.client_capabilities().await.is_some());
+            peer.notify_tool_list_changed().await?;
+            tokio::time::sleep(self.delay).await;
+            let roots = peer.list_roots().await?;
+            let message = peer
+                .create_message(crate::types::CreateMessageRequest {
+                    messages: vec![],
+                    model_preferences: None,
+                    system_prompt: None,
+                    include_context: None,
+                    temperature: None,
+                    max_tokens: 16,
+                    stop_sequences: None,
+                    metadata: None,
+                })
+                .await?;
+            Ok(serde_json::json!({"root": roots.roots[0].uri, "model": message.model}))
+        }
+    }
+
+    struct SamplingClientHandler;
+
+    #[async_trait]
+    impl crate::client::ClientHandler for SamplingClientHandler {
+        async fn list_roots(&self) -> Result<serde_json::Value, Error> {
+            Ok(serde_json::json!({"roots": [{"uri": "file:///workspace"}]}))
+        }
+
+        async fn create_message(&self, _params: serde_json::Value) -> Result<serde_json::Value, Error> {
+            Ok(serde_json::json!({
+                "role": "assistant",
+                "content": {"type": "text", "text": "hi"},
+                "model": "test-model"
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_peer_requests_and_notifications() {
+        let (server_transport, client_transport) = duplex();
+        let server = Server::new(Arc::new(server_transport), Arc::new(PeerHandler { delay: Duration::ZERO }));
+        let server_handle = tokio::spawn(async move { server.start().await });
+
+        let client = crate::client::Client::with_handler(
+            Arc::new(client_transport),
+            None,
+            Arc::new(SamplingClientHandler),
+        );
+        let mut list_changed = client.subscribe_notifications_for("notifications/tools/list_changed");
+        client
+            .initialize(
+                Implementation {
+                    name: "test-client".to_string(),
+                    version: "0.1.0".to_string(),
+                    title: None,
+                },
+                ClientCapabilities::default(),
+            )
+            .await
+            .unwrap();
+
+        let result = client.request("ask", None).await.unwrap();
+        assert_eq!(result, serde_json::json!({"root": "file:///workspace", "model": "test-model"}));
+        assert!(tokio::time::timeout(Duration::from_secs(5), list_changed.next())
+            .await
+            .unwrap()
+            .is_some());
+
+        let _ = client.notify("exit", None).await;
+        let _ = tokio::time::timeout(Duration::from_secs(1), server_handle).await;
+    }
+
+    #[tokio::test]
+    async fn test_peer_requests_complete_while_draining() {
+        let (server_transport, client_transport) = duplex();
+        let server = Server::new(Arc::new(server_transport), Arc::new(PeerHandler {
+            delay: Duration::from_millis(200),
+        }));
+        let server_handle = tokio::spawn(async move { server.start().await });
+
+        let client = Arc::new(crate::client::Client::with_handler(
+            Arc::new(client_transport),
+            None,
+            Arc::new(SamplingClientHandler),
+        ));
+        client
+            .initialize(
+                Implementation {
+                    name: "test-client".to_string(),
+                    version: "0.1.0".to_string(),
+                    title: None,
+                },
+                ClientCapabilities::default(),
+            )
+            .await
+            .unwrap();
+
+        // `exit` arrives before the handler calls back into the client.
+        let mut list_changed = client.subscribe_notifications_for("notifications/tools/list_changed");
+        let requester = client.clone();
+        let ask = tokio::spawn(async move { requester.request("ask", None).await });
+        tokio::time::timeout(Duration::from_secs(5), list_changed.next())
+            .await
+            .unwrap();
+        let _ = client.notify("exit", None).await;
+
+        let result = tokio::time::timeout(Duration::from_secs(5), ask)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, serde_json::json!({"root": "file:///workspace", "model": "test-model"}));
+        assert!(tokio::time::timeout(Duration::from_secs(1), server_handle)
+            .await
+            .unwrap()
+            .unwrap()
+            .is_ok());
     }
 }
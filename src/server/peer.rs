@@ -0,0 +1,168 @@
+//! Talking back to the client from a server.
+//!
+//! A [`Peer`] sends notifications and requests to the client over the transport
+//! of a [`Server`](super::Server). Handlers receive one in their
+//! [`RequestContext`]; [`Server::peer`](super::Server::peer) hands out one for
+//! use outside of a request, e.g. to announce that the tool list changed.
+
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    error::Error,
+    protocol::{Notification, Response},
+    requester::{RequestOptions, Requester},
+    transport::{Message, Transport},
+    types::{
+        ClientCapabilities, CreateMessageRequest, CreateMessageResult, ListRootsResult,
+        LoggingLevel, LoggingMessageNotification,
+    },
+};
+
+use super::ProgressReporter;
+
+/// Handle to the client on the other end of a [`Server`](super::Server).
+///
+/// Responses to requests sent through a peer are routed back by the server loop,
+/// so they only complete while [`Server::start`](super::Server::start) is running.
+/// Cloning is cheap and yields a handle to the same connection.
+#[derive(Clone)]
+pub struct Peer {
+    transport: Arc<dyn Transport>,
+    requester: Requester,
+    client_capabilities: Arc<RwLock<Option<ClientCapabilities>>>,
+}
+
+impl Peer {
+    pub(super) fn new(transport: Arc<dyn Transport>) -> Self {
+        Self {
+            requester: Requester::new(transport.clone()),
+            transport,
+            client_capabilities: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Returns the capabilities the client sent in `initialize`, once initialized.
+    pub async fn client_capabilities(&self) -> Option<ClientCapabilities> {
+        self.client_capabilities.read().await.clone()
+    }
+
+    pub(super) async fn set_client_capabilities(&self, capabilities: ClientCapabilities) {
+        *self.client_capabilities.write().await = Some(capabilities);
+    }
+
+    /// Sends a notification to the client.
+    pub async fn notify(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+    ) -> Result<(), Error> {
+        self.transport
+            .send(Message::Notification(Notification::new(method, params)))
+            .await
+    }
+
+    /// Sends a request to the client and waits for its result.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transport fails, the client returns an error, the
+    /// connection closes, or no response is received within 120 seconds. On timeout
+    /// the client is told to stop with a `notifications/cancelled` notification.
+    pub async fn request(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, Error> {
+        self.requester
+            .request(method, params, RequestOptions::default())
+            .await
+    }
+
+    /// Asks the client to sample a message from its model with `sampling/createMessage`.
+    ///
+    /// The client must have advertised the `sampling` capability.
+    pub async fn create_message(
+        &self,
+        request: CreateMessageRequest,
+    ) -> Result<CreateMessageResult, Error> {
+        let result = self
+            .request(
+                "sampling/createMessage",
+                Some(serde_json::to_value(request)?),
+            )
+            .await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Asks the client for the roots the server may operate on with `roots/list`.
+    pub async fn list_roots(&self) -> Result<ListRootsResult, Error> {
+        let result = self.request("roots/list", None).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Sends a log message as `notifications/message`.
+    pub async fn log(
+        &self,
+        level: LoggingLevel,
+        logger: Option<String>,
+        data: serde_json::Value,
+    ) -> Result<(), Error> {
+        let params = LoggingMessageNotification {
+            level,
+            logger,
+            data,
+        };
+        self.notify("notifications/message", Some(serde_json::to_value(params)?))
+            .await
+    }
+
+    /// Tells the client the tool list changed, so it should call `tools/list` again.
+    pub async fn notify_tool_list_changed(&self) -> Result<(), Error> {
+        self.notify("notifications/tools/list_changed", None).await
+    }
+
+    /// Tells the client the resource list changed.
+    pub async fn notify_resource_list_changed(&self) -> Result<(), Error> {
+        self.notify("notifications/resources/list_changed", None)
+            .await
+    }
+
+    /// Tells the client the prompt list changed.
+    pub async fn notify_prompt_list_changed(&self) -> Result<(), Error> {
+        self.notify("notifications/prompts/list_changed", None).await
+    }
+
+    /// Tells a subscribed client that the resource at `uri` changed.
+    pub async fn notify_resource_updated(&self, uri: &str) -> Result<(), Error> {
+        self.notify(
+            "notifications/resources/updated",
+            Some(serde_json::json!({ "uri": uri })),
+        )
+        .await
+    }
+
+    /// Hands a response from the client to the request waiting for it.
+    pub(super) async fn resolve(&self, response: Response) {
+        self.requester.resolve(response).await
+    }
+
+    /// Fails every request still waiting for a response; used when the server stops.
+    pub(super) async fn close(&self) {
+        self.requester.close().await
+    }
+}
+
+/// What a handler gets to know about the request it is handling.
+#[derive(Clone)]
+pub struct RequestContext {
+    /// Handle for sending notifications and requests to the client.
+    pub peer: Peer,
+    /// Set if the client asked for progress by setting `_meta.progressToken`.
+    pub progress: Option<ProgressReporter>,
+    /// Cancelled once the client sends `notifications/cancelled` for the request.
+    /// The handler future is dropped at that point too, so this is only needed to
+    /// stop work the handler spawned.
+    pub cancellation: CancellationToken,
+}
@@ -0,0 +1,724 @@
+//! Declarative registration of tools, resources and prompts.
+//!
+//! A [`Router`] collects typed Rust functions and answers the standard `tools/*`,
+//! `resources/*` and `prompts/*` methods for them, so a server does not have to
+//! match method names by hand:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use alou::server::{Router, Server};
+//! use alou::transport::stdio::StdioTransport;
+//! use alou::types::{CallToolResult, MessageContent};
+//!
+//! #[derive(serde::Deserialize, schemars::JsonSchema)]
+//! struct EchoArgs {
+//!     /// Text to echo back
+//!     text: String,
+//! }
+//!
+//! # async fn run() -> Result<(), alou::Error> {
+//! let router = Router::new().tool("echo", "Echoes its input", |args: EchoArgs| async move {
+//!     Ok(CallToolResult {
+//!         content: vec![MessageContent::text(args.text)],
+//!         structured_content: None,
+//!         is_error: false,
+//!     })
+//! });
+//! let transport = StdioTransport::with_streams(tokio::io::stdin(), tokio::io::stdout())?;
+//! Server::new(Arc::new(transport), Arc::new(router)).start().await
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use std::sync::Arc;
+
+use super::{RequestContext, ServerHandler};
+use crate::{
+    error::{Error, ErrorCode},
+    types::{
+        CallToolRequest, CallToolResult, ClientCapabilities, GetPromptRequest, GetPromptResult,
+        Implementation, ListPromptsResult, ListResourcesResult, ListToolsResult, MessageContent,
+        Prompt, PromptArgument, PromptsCapability, ReadResourceResult, Resource, ResourceContents,
+        ResourceRequest, ResourcesCapability, ServerCapabilities, Tool, ToolsCapability,
+    },
+};
+
+/// Calls a tool with its raw JSON arguments and the context of the request, if any.
+type ToolFn = Arc<
+    dyn Fn(
+            serde_json::Value,
+            Option<RequestContext>,
+        ) -> BoxFuture<'static, Result<CallToolResult, Error>>
+        + Send
+        + Sync,
+>;
+
+/// Reads the contents of a resource.
+type ResourceFn =
+    Arc<dyn Fn() -> BoxFuture<'static, Result<Vec<ResourceContents>, Error>> + Send + Sync>;
+
+/// Renders a prompt from its raw JSON arguments and the context of the request, if any.
+type PromptFn = Arc<
+    dyn Fn(
+            serde_json::Value,
+            Option<RequestContext>,
+        ) -> BoxFuture<'static, Result<GetPromptResult, Error>>
+        + Send
+        + Sync,
+>;
+
+/// A [`ServerHandler`] built from registered tools, resources and prompts.
+///
+/// Arguments are deserialized with serde and described to clients with the JSON
+/// Schema derived by [`JsonSchema`], so argument types should be structs.
+/// Capabilities are advertised for each kind of item that has been registered.
+/// Tools and prompts registered with [`Router::tool_with_context`] and
+/// [`Router::prompt_with_context`] also get the [`RequestContext`] of the call.
+#[derive(Default, Clone)]
+pub struct Router {
+    tools: Vec<(Tool, ToolFn)>,
+    resources: Vec<(Resource, ResourceFn)>,
+    prompts: Vec<(Prompt, PromptFn)>,
+}
+
+impl Router {
+    /// Creates a router with nothing registered.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a tool whose arguments are deserialized into `A`.
+    ///
+    /// An `Err` returned by `handler` is reported to the client as a result with
+    /// `isError` set, so the model can see what went wrong.
+    pub fn tool<A, F, Fut>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) -> Self
+    where
+        A: DeserializeOwned + JsonSchema + Send + 'static,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<CallToolResult, Error>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        let call: ToolFn = Arc::new(move |arguments, _context| {
+            let handler = handler.clone();
+            Box::pin(async move { handler(parse_arguments(arguments)?).await })
+        });
+        self.tools
+            .push((tool_definition::<A>(name, description), call));
+        self
+    }
+
+    /// Registers a tool like [`Router::tool`] whose handler also receives the
+    /// [`RequestContext`], to report progress, notice cancellation or call back
+    /// into the client through its [`Peer`](super::Peer).
+    pub fn tool_with_context<A, F, Fut>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) -> Self
+    where
+        A: DeserializeOwned + JsonSchema + Send + 'static,
+        F: Fn(A, RequestContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<CallToolResult, Error>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        let call: ToolFn = Arc::new(move |arguments, context| {
+            let handler = handler.clone();
+            Box::pin(async move {
+                handler(parse_arguments(arguments)?, require_context(context)?).await
+            })
+        });
+        self.tools
+            .push((tool_definition::<A>(name, description), call));
+        self
+    }
+
+    /// Registers a resource whose contents are produced by `handler` on every read.
+    pub fn resource<F, Fut>(mut self, resource: Resource, handler: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Vec<ResourceContents>, Error>> + Send + 'static,
+    {
+        let read: ResourceFn = Arc::new(move || Box::pin(handler()));
+        self.resources.push((resource, read));
+        self
+    }
+
+    /// Registers a prompt whose arguments are deserialized into `A`.
+    ///
+    /// Prompt arguments are always strings, so the fields of `A` should be `String`
+    /// or `Option<String>`; optional fields are advertised as not required.
+    pub fn prompt<A, F, Fut>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) -> Self
+    where
+        A: DeserializeOwned + JsonSchema + Send + 'static,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<GetPromptResult, Error>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        let get: PromptFn = Arc::new(move |arguments, _context| {
+            let handler = handler.clone();
+            Box::pin(async move { handler(parse_arguments(arguments)?).await })
+        });
+        self.prompts
+            .push((prompt_definition::<A>(name, description), get));
+        self
+    }
+
+    /// Registers a prompt like [`Router::prompt`] whose handler also receives the
+    /// [`RequestContext`].
+    pub fn prompt_with_context<A, F, Fut>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) -> Self
+    where
+        A: DeserializeOwned + JsonSchema + Send + 'static,
+        F: Fn(A, RequestContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<GetPromptResult, Error>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        let get: PromptFn = Arc::new(move |arguments, context| {
+            let handler = handler.clone();
+            Box::pin(async move {
+                handler(parse_arguments(arguments)?, require_context(context)?).await
+            })
+        });
+        self.prompts
+            .push((prompt_definition::<A>(name, description), get));
+        self
+    }
+
+    /// The capabilities matching what has been registered.
+    pub fn capabilities(&self) -> ServerCapabilities {
+        ServerCapabilities {
+            tools: (!self.tools.is_empty()).then(ToolsCapability::default),
+            resources: (!self.resources.is_empty()).then(ResourcesCapability::default),
+            prompts: (!self.prompts.is_empty()).then(PromptsCapability::default),
+            ..Default::default()
+        }
+    }
+
+    async fn call_tool(
+        &self,
+        params: Option<serde_json::Value>,
+        context: Option<RequestContext>,
+    ) -> Result<CallToolResult, Error> {
+        let request: CallToolRequest = parse_arguments(params.unwrap_or_default())?;
+        let (_, call) = self
+            .tools
+            .iter()
+            .find(|(tool, _)| tool.name == request.name)
+            .ok_or_else(|| {
+                Error::protocol(
+                    ErrorCode::InvalidParams,
+                    format!("Unknown tool: {}", request.name),
+                )
+            })?;
+        match call(request.arguments, context).await {
+            Ok(result) => Ok(result),
+            // Malformed arguments are a protocol error, not a failed tool run.
+            Err(
+                e @ Error::Protocol {
+                    code: ErrorCode::InvalidParams,
+                    ..
+                },
+            ) => Err(e),
+            Err(e) => Ok(CallToolResult {
+                content: vec![MessageContent::text(e.to_string())],
+                structured_content: None,
+                is_error: true,
+            }),
+        }
+    }
+
+    async fn read_resource(
+        &self,
+        params: Option<serde_json::Value>,
+    ) -> Result<ReadResourceResult, Error> {
+        let request: ResourceRequest = parse_arguments(params.unwrap_or_default())?;
+        let (_, read) = self
+            .resources
+            .iter()
+            .find(|(resource, _)| resource.uri == request.uri)
+            .ok_or_else(|| {
+                Error::protocol(
+                    ErrorCode::InvalidParams,
+                    format!("Unknown resource: {}", request.uri),
+                )
+            })?;
+        Ok(ReadResourceResult {
+            contents: read().await?,
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        params: Option<serde_json::Value>,
+        context: Option<RequestContext>,
+    ) -> Result<GetPromptResult, Error> {
+        let request: GetPromptRequest = parse_arguments(params.unwrap_or_default())?;
+        let (_, get) = self
+            .prompts
+            .iter()
+            .find(|(prompt, _)| prompt.name == request.name)
+            .ok_or_else(|| {
+                Error::protocol(
+                    ErrorCode::InvalidParams,
+                    format!("Unknown prompt: {}", request.name),
+                )
+            })?;
+        get(
+            serde_json::to_value(request.arguments.unwrap_or_default())?,
+            context,
+        )
+        .await
+    }
+
+    /// Answers `method`, passing `context` on to tool and prompt handlers.
+    async fn route(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        context: Option<RequestContext>,
+    ) -> Result<serde_json::Value, Error> {
+        // Every item is returned on the first page, so no `nextCursor` is ever set.
+        let result = match method {
+            "tools/list" => serde_json::to_value(ListToolsResult {
+                tools: self.tools.iter().map(|(tool, _)| tool.clone()).collect(),
+                next_cursor: None,
+            })?,
+            "tools/call" => serde_json::to_value(self.call_tool(params, context).await?)?,
+            "resources/list" => serde_json::to_value(ListResourcesResult {
+                resources: self
+                    .resources
+                    .iter()
+                    .map(|(resource, _)| resource.clone())
+                    .collect(),
+                next_cursor: None,
+            })?,
+            "resources/read" => serde_json::to_value(self.read_resource(params).await?)?,
+            "prompts/list" => serde_json::to_value(ListPromptsResult {
+                prompts: self
+                    .prompts
+                    .iter()
+                    .map(|(prompt, _)| prompt.clone())
+                    .collect(),
+                next_cursor: None,
+            })?,
+            "prompts/get" => serde_json::to_value(self.get_prompt(params, context).await?)?,
+            _ => {
+                return Err(Error::protocol(
+                    ErrorCode::MethodNotFound,
+                    format!("Method not found: {}", method),
+                ))
+            }
+        };
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl ServerHandler for Router {
+    async fn initialize(
+        &self,
+        _implementation: Implementation,
+        _capabilities: ClientCapabilities,
+    ) -> Result<ServerCapabilities, Error> {
+        Ok(self.capabilities())
+    }
+
+    async fn shutdown(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Answers without a [`RequestContext`]; tools and prompts registered with
+    /// one fail when called this way.
+    async fn handle_method(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, Error> {
+        self.route(method, params, None).await
+    }
+
+    async fn handle_method_with_context(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        context: RequestContext,
+    ) -> Result<serde_json::Value, Error> {
+        self.route(method, params, Some(context)).await
+    }
+}
+
+/// Describes a tool whose arguments are deserialized into `A`.
+fn tool_definition<A: JsonSchema>(name: impl Into<String>, description: impl Into<String>) -> Tool {
+    Tool {
+        name: name.into(),
+        title: None,
+        description: Some(description.into()),
+        input_schema: input_schema::<A>(),
+        output_schema: None,
+        annotations: None,
+    }
+}
+
+/// Describes a prompt whose arguments are deserialized into `A`.
+fn prompt_definition<A: JsonSchema>(
+    name: impl Into<String>,
+    description: impl Into<String>,
+) -> Prompt {
+    Prompt {
+        name: name.into(),
+        title: None,
+        description: Some(description.into()),
+        arguments: Some(prompt_arguments(&input_schema::<A>())),
+    }
+}
+
+/// The context a handler registered with one needs, which only a [`Server`](super::Server)
+/// provides.
+fn require_context(context: Option<RequestContext>) -> Result<RequestContext, Error> {
+    context.ok_or_else(|| {
+        Error::protocol(
+            ErrorCode::InternalError,
+            "This handler needs a request context and must be called through a Server",
+        )
+    })
+}
+
+/// The JSON Schema of `A`, without the `$schema` keyword.
+fn input_schema<A: JsonSchema>() -> serde_json::Value {
+    let mut schema = serde_json::to_value(schemars::schema_for!(A)).unwrap_or_default();
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("$schema");
+    }
+    schema
+}
+
+/// Describes the properties of an object schema as prompt arguments.
+fn prompt_arguments(schema: &serde_json::Value) -> Vec<PromptArgument> {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(|required| required.as_array())
+        .map(|required| required.iter().filter_map(|name| name.as_str()).collect())
+        .unwrap_or_default();
+    let properties = schema
+        .get("properties")
+        .and_then(|properties| properties.as_object())
+        .cloned()
+        .unwrap_or_default();
+    properties
+        .into_iter()
+        .map(|(name, property)| PromptArgument {
+            required: Some(required.contains(&name.as_str())),
+            description: property
+                .get("description")
+                .and_then(|description| description.as_str())
+                .map(str::to_string),
+            title: None,
+            name,
+        })
+        .collect()
+}
+
+/// Deserializes request arguments, rejecting malformed ones as invalid params.
+fn parse_arguments<A: DeserializeOwned>(arguments: serde_json::Value) -> Result<A, Error> {
+    serde_json::from_value(arguments)
+        .map_err(|e| Error::protocol(ErrorCode::InvalidParams, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, JsonSchema)]
+    struct TransferArgs {
+        /// Recipient address
+        to: String,
+        amount: f64,
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    struct StatusArgs {
+        verbose: Option<bool>,
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    struct ExplainArgs {
+        /// Transaction hash
+        hash: String,
+        language: Option<String>,
+    }
+
+    fn router() -> Router {
+        Router::new()
+            .tool("transfer", "Sends funds", |args: TransferArgs| async move {
+                if args.amount <= 0.0 {
+                    return Err(Error::Other("amount must be positive".to_string()));
+                }
+                Ok(CallToolResult {
+                    content: vec![MessageContent::text(format!(
+                        "sent {} to {}",
+                        args.amount, args.to
+                    ))],
+                    structured_content: None,
+                    is_error: false,
+                })
+            })
+            .tool(
+                "status",
+                "Reports wallet status",
+                |args: StatusArgs| async move {
+                    let status = if args.verbose.unwrap_or(false) {
+                        "synced at block 42"
+                    } else {
+                        "synced"
+                    };
+                    Ok(CallToolResult {
+                        content: vec![MessageContent::text(status)],
+                        structured_content: None,
+                        is_error: false,
+                    })
+                },
+            )
+            .resource(
+                Resource {
+                    uri: "wallet://networks".to_string(),
+                    name: "networks".to_string(),
+                    title: None,
+                    description: None,
+                    mime_type: Some("text/plain".to_string()),
+                    annotations: None,
+                    size: None,
+                },
+                || async {
+                    Ok(vec![ResourceContents::Text {
+                        uri: "wallet://networks".to_string(),
+                        mime_type: Some("text/plain".to_string()),
+                        text: "mainnet\ntestnet".to_string(),
+                    }])
+                },
+            )
+            .prompt(
+                "explain",
+                "Explains a transaction",
+                |args: ExplainArgs| async move {
+                    Ok(GetPromptResult {
+                        description: None,
+                        messages: vec![crate::types::PromptMessage {
+                            role: crate::types::Role::User,
+                            content: MessageContent::text(format!(
+                                "Explain {} in {}",
+                                args.hash,
+                                args.language.as_deref().unwrap_or("English")
+                            )),
+                        }],
+                    })
+                },
+            )
+    }
+
+    #[tokio::test]
+    async fn test_router_capabilities_and_lists() {
+        let router = router();
+        let capabilities = router
+            .initialize(
+                Implementation {
+                    name: "test".to_string(),
+                    version: "0.1.0".to_string(),
+                    title: None,
+                },
+                ClientCapabilities::default(),
+            )
+            .await
+            .unwrap();
+        assert!(capabilities.tools.is_some());
+        assert!(capabilities.resources.is_some());
+        assert!(capabilities.prompts.is_some());
+        assert!(Router::new().capabilities().tools.is_none());
+
+        let tools: ListToolsResult =
+            serde_json::from_value(router.handle_method("tools/list", None).await.unwrap())
+                .unwrap();
+        let schema = &tools.tools[0].input_schema;
+        assert_eq!(schema["type"], "object");
+        assert_eq!(
+            schema["properties"]["to"]["description"],
+            "Recipient address"
+        );
+        assert!(schema.get("$schema").is_none());
+
+        let prompts: ListPromptsResult =
+            serde_json::from_value(router.handle_method("prompts/list", None).await.unwrap())
+                .unwrap();
+        let arguments = prompts.prompts[0].arguments.clone().unwrap();
+        let hash = arguments
+            .iter()
+            .find(|argument| argument.name == "hash")
+            .unwrap();
+        assert_eq!(hash.required, Some(true));
+        assert_eq!(hash.description.as_deref(), Some("Transaction hash"));
+        let language = arguments
+            .iter()
+            .find(|argument| argument.name == "language")
+            .unwrap();
+        assert_eq!(language.required, Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_router_dispatch() {
+        let router = router();
+
+        let result = router
+            .handle_method(
+                "tools/call",
+                Some(serde_json::json!({"name": "transfer", "arguments": {"to": "0xabc", "amount": 1.5}})),
+            )
+            .await
+            .unwrap();
+        let result: CallToolResult = serde_json::from_value(result).unwrap();
+        assert_eq!(result.text(), "sent 1.5 to 0xabc");
+
+        // Clients may leave out `arguments` entirely.
+        let result = router
+            .handle_method("tools/call", Some(serde_json::json!({"name": "status"})))
+            .await
+            .unwrap();
+        let result: CallToolResult = serde_json::from_value(result).unwrap();
+        assert_eq!(result.text(), "synced");
+
+        // A failing tool is reported in the result rather than as a protocol error.
+        let result = router
+            .handle_method(
+                "tools/call",
+                Some(serde_json::json!({"name": "transfer", "arguments": {"to": "0xabc", "amount": 0}})),
+            )
+            .await
+            .unwrap();
+        let result: CallToolResult = serde_json::from_value(result).unwrap();
+        assert!(result.is_error);
+
+        let invalid = router
+            .handle_method(
+                "tools/call",
+                Some(serde_json::json!({"name": "transfer", "arguments": {}})),
+            )
+            .await;
+        assert!(matches!(
+            invalid,
+            Err(Error::Protocol {
+                code: ErrorCode::InvalidParams,
+                ..
+            })
+        ));
+
+        let read = router
+            .handle_method(
+                "resources/read",
+                Some(serde_json::json!({"uri": "wallet://networks"})),
+            )
+            .await
+            .unwrap();
+        assert_eq!(read["contents"][0]["text"], "mainnet\ntestnet");
+
+        let prompt = router
+            .handle_method(
+                "prompts/get",
+                Some(serde_json::json!({"name": "explain", "arguments": {"hash": "0x1"}})),
+            )
+            .await
+            .unwrap();
+        let prompt: GetPromptResult = serde_json::from_value(prompt).unwrap();
+        assert_eq!(
+            prompt.messages[0].content.as_text(),
+            Some("Explain 0x1 in English")
+        );
+
+        let unknown = router.handle_method("tools/unknown", None).await;
+        assert!(matches!(
+            unknown,
+            Err(Error::Protocol {
+                code: ErrorCode::MethodNotFound,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_context_reaches_tool_handlers() {
+        use crate::client::{Client, ToolCallUpdate};
+        use futures::StreamExt;
+
+        let router = Router::new().tool_with_context(
+            "sync",
+            "Syncs the wallet",
+            |_: StatusArgs, context: RequestContext| async move {
+                assert!(context.peer.client_capabilities().await.is_some());
+                assert!(!context.cancellation.is_cancelled());
+                if let Some(progress) = &context.progress {
+                    progress.report(1.0, Some(1.0), None).await?;
+                }
+                Ok(CallToolResult {
+                    content: vec![MessageContent::text("synced")],
+                    structured_content: None,
+                    is_error: false,
+                })
+            },
+        );
+
+        // Called directly there is no context to hand to the tool.
+        let result = router
+            .handle_method("tools/call", Some(serde_json::json!({"name": "sync"})))
+            .await
+            .unwrap();
+        let result: CallToolResult = serde_json::from_value(result).unwrap();
+        assert!(result.is_error);
+
+        let (server_side, client_side) = crate::transport::memory::duplex();
+        let server = super::super::Server::new(Arc::new(server_side), Arc::new(router));
+        tokio::spawn(async move { server.start().await });
+        let client = Client::new(Arc::new(client_side), None);
+        client
+            .initialize(
+                Implementation {
+                    name: "test".to_string(),
+                    version: "0.1.0".to_string(),
+                    title: None,
+                },
+                ClientCapabilities::default(),
+            )
+            .await
+            .unwrap();
+
+        let updates: Vec<_> = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            client
+                .call_tool_with_progress("sync", serde_json::json!({}))
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(updates[0], ToolCallUpdate::Progress(_)));
+        match updates.last() {
+            Some(ToolCallUpdate::Finished(Ok(result))) => assert_eq!(result.text(), "synced"),
+            other => panic!("Expected a successful result last, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,885 @@
+//! Streamable HTTP transport.
+//!
+//! Client-to-server messages are sent as HTTP `POST` requests carrying a single
+//! JSON-RPC message. Responses to requests come back in the body of the `POST`
+//! (either as `application/json` or as a `text/event-stream`), while messages the
+//! server initiates on its own are delivered over a long-lived Server-Sent Events
+//! stream opened with `GET` on the same endpoint.
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, oneshot, watch, Mutex, RwLock};
+use warp::http::StatusCode;
+use warp::Filter;
+
+use crate::{
+    error::{Error, ErrorCode},
+    protocol::{RequestId, Response, ResponseError},
+    transport::{Message, Transport},
+};
+
+/// Header used to carry the session identifier between client and server.
+pub const SESSION_ID_HEADER: &str = "mcp-session-id";
+
+/// Header carrying the negotiated protocol version on requests after initialization.
+pub const PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+
+/// Path the server-side listener serves the MCP endpoint on.
+pub const DEFAULT_ENDPOINT_PATH: &str = "mcp";
+
+/// A client transport talking to a remote MCP server over Streamable HTTP.
+pub struct HttpClientTransport {
+    /// Full URL of the MCP endpoint, e.g. `http://localhost:8080/mcp`.
+    endpoint: String,
+    /// The underlying HTTP client.
+    http: reqwest::Client,
+    /// Session identifier assigned by the server, if any.
+    session_id: Arc<RwLock<Option<String>>>,
+    /// Protocol version negotiated during initialization, sent as a header afterwards.
+    protocol_version: std::sync::RwLock<Option<String>>,
+    /// Sender feeding messages parsed from HTTP responses and the event stream.
+    incoming_tx: mpsc::UnboundedSender<Result<Message, Error>>,
+    /// Receiver handed out through `receive()`.
+    incoming_rx: Arc<Mutex<mpsc::UnboundedReceiver<Result<Message, Error>>>>,
+    /// Task reading the server-initiated event stream, started after the first `POST`.
+    event_stream: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+impl HttpClientTransport {
+    /// Creates a new transport for the MCP endpoint at `endpoint`.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self::with_client(endpoint, reqwest::Client::new())
+    }
+
+    /// Creates a new transport using a preconfigured `reqwest::Client`
+    /// (for custom headers, proxies, TLS settings and so on).
+    pub fn with_client(endpoint: impl Into<String>, http: reqwest::Client) -> Self {
+        let (incoming_tx, incoming_rx) = mpsc::unbounded_channel();
+        Self {
+            endpoint: endpoint.into(),
+            http,
+            session_id: Arc::new(RwLock::new(None)),
+            protocol_version: std::sync::RwLock::new(None),
+            incoming_tx,
+            incoming_rx: Arc::new(Mutex::new(incoming_rx)),
+            event_stream: Mutex::new(None),
+        }
+    }
+
+    /// Adds the session id and protocol version headers, once known, to a request.
+    async fn with_session_headers(
+        &self,
+        mut request: reqwest::RequestBuilder,
+    ) -> reqwest::RequestBuilder {
+        if let Some(id) = self.session_id.read().await.as_ref() {
+            request = request.header(SESSION_ID_HEADER, id);
+        }
+        let version = self.protocol_version.read().unwrap().clone();
+        if let Some(version) = version {
+            request = request.header(PROTOCOL_VERSION_HEADER, version);
+        }
+        request
+    }
+
+    /// Returns the session identifier assigned by the server, if any.
+    pub async fn session_id(&self) -> Option<String> {
+        self.session_id.read().await.clone()
+    }
+
+    /// Opens the `GET` event stream for server-initiated messages, once.
+    async fn ensure_event_stream(&self) {
+        let mut event_stream = self.event_stream.lock().await;
+        if event_stream.is_some() {
+            return;
+        }
+
+        let request = self
+            .with_session_headers(
+                self.http
+                    .get(&self.endpoint)
+                    .header(reqwest::header::ACCEPT, "text/event-stream"),
+            )
+            .await;
+
+        let tx = self.incoming_tx.clone();
+        *event_stream = Some(tokio::spawn(async move {
+            match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    tracing::debug!("Opened HTTP event stream");
+                    forward_event_stream(response, tx).await;
+                }
+                Ok(response) => {
+                    tracing::debug!(
+                        status = %response.status(),
+                        "Server does not offer an event stream"
+                    );
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to open HTTP event stream");
+                }
+            }
+        }));
+    }
+}
+
+#[async_trait]
+impl Transport for HttpClientTransport {
+    /// Sends a message by `POST`ing it to the endpoint and forwarding whatever the
+    /// server returns in the response body to the receive stream.
+    async fn send(&self, message: Message) -> Result<(), Error> {
+        let request = self
+            .with_session_headers(
+                self.http
+                    .post(&self.endpoint)
+                    .header(
+                        reqwest::header::ACCEPT,
+                        "application/json, text/event-stream",
+                    )
+                    .json(&message),
+            )
+            .await;
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| Error::Transport(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::Transport(format!(
+                "HTTP {} from {}: {}",
+                status, self.endpoint, body
+            )));
+        }
+
+        if let Some(id) = response
+            .headers()
+            .get(SESSION_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            let mut session_id = self.session_id.write().await;
+            if session_id.as_deref() != Some(id) {
+                tracing::debug!(session_id = %id, "Received HTTP session id");
+                *session_id = Some(id.to_string());
+            }
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+
+        if content_type.starts_with("text/event-stream") {
+            tokio::spawn(forward_event_stream(response, self.incoming_tx.clone()));
+        } else if status != reqwest::StatusCode::ACCEPTED {
+            let body = response
+                .text()
+                .await
+                .map_err(|e| Error::Transport(e.to_string()))?;
+            if !body.trim().is_empty() {
+                let message = serde_json::from_str::<Message>(&body).map_err(Error::from);
+                let _ = self.incoming_tx.send(message);
+            }
+        }
+
+        self.ensure_event_stream().await;
+        Ok(())
+    }
+
+    /// Provides a stream of messages received in `POST` responses and on the event stream.
+    fn receive(&self) -> Pin<Box<dyn Stream<Item = Result<Message, Error>> + Send>> {
+        let rx = self.incoming_rx.clone();
+        Box::pin(async_stream::stream! {
+            let mut rx = rx.lock().await;
+            while let Some(msg) = rx.recv().await {
+                yield msg;
+            }
+        })
+    }
+
+    /// Stops the event stream and asks the server to terminate the session.
+    async fn close(&self) -> Result<(), Error> {
+        if let Some(handle) = self.event_stream.lock().await.take() {
+            handle.abort();
+        }
+        if let Some(id) = self.session_id.write().await.take() {
+            // Servers may refuse explicit termination, so failures are only logged.
+            if let Err(e) = self
+                .http
+                .delete(&self.endpoint)
+                .header(SESSION_ID_HEADER, id)
+                .send()
+                .await
+            {
+                tracing::debug!(error = %e, "Failed to terminate HTTP session");
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends `version` in the `mcp-protocol-version` header from now on.
+    fn set_protocol_version(&self, version: &str) {
+        *self.protocol_version.write().unwrap() = Some(version.to_string());
+    }
+}
+
+/// Reads a Server-Sent Events body and forwards every `data` payload as a message.
+async fn forward_event_stream(
+    response: reqwest::Response,
+    tx: mpsc::UnboundedSender<Result<Message, Error>>,
+) {
+    let mut body = response.bytes_stream();
+    let mut buffer = String::new();
+    let mut data = String::new();
+
+    while let Some(chunk) = body.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                let _ = tx.send(Err(Error::Transport(e.to_string())));
+                return;
+            }
+        };
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+        while let Some(pos) = buffer.find('\n') {
+            let line = buffer[..pos].trim_end_matches('\r').to_string();
+            buffer.drain(..=pos);
+
+            if line.is_empty() {
+                // A blank line terminates the current event.
+                if !data.is_empty() {
+                    let message = serde_json::from_str::<Message>(&data).map_err(Error::from);
+                    if tx.send(message).is_err() {
+                        return;
+                    }
+                    data.clear();
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                if !data.is_empty() {
+                    data.push('\n');
+                }
+                data.push_str(value.strip_prefix(' ').unwrap_or(value));
+            }
+            // `event:`, `id:`, `retry:` and comment lines carry nothing we need.
+        }
+    }
+    tracing::debug!("HTTP event stream ended");
+}
+
+/// A `POST` waiting for the server's answer to the requests it carried.
+struct PendingPost {
+    /// Ids of every request in the `POST`, one for a single request.
+    ids: Vec<RequestId>,
+    reply: oneshot::Sender<Message>,
+}
+
+/// `POST`s waiting for a response. No request id is in flight twice, so any id of
+/// a response identifies the `POST` it belongs to.
+type PendingPosts = Arc<Mutex<Vec<PendingPost>>>;
+
+/// The single session served by [`HttpServerTransport`].
+enum Session {
+    /// No `initialize` request has arrived yet.
+    Pending,
+    /// Started by `initialize`; every later request must carry this id.
+    Active(String),
+    /// Terminated by the client with `DELETE`.
+    Closed,
+}
+
+/// State shared between the server transport and its HTTP handlers.
+#[derive(Clone)]
+struct ServerState {
+    session: Arc<std::sync::Mutex<Session>>,
+    /// Flipped to `true` once the client closes the session.
+    ended: Arc<watch::Sender<bool>>,
+    incoming_tx: mpsc::UnboundedSender<Result<Message, Error>>,
+    pending: PendingPosts,
+    events: broadcast::Sender<Message>,
+    closed: watch::Receiver<bool>,
+}
+
+/// A server transport that accepts Streamable HTTP connections from a single client session.
+///
+/// Requests posted by the client are answered in the body of the same `POST`, while
+/// notifications and requests sent by the server go out over the `GET` event stream.
+/// The session starts with the `initialize` request; afterwards requests must carry
+/// its `mcp-session-id`, and a `DELETE` ends it along with the receive stream.
+pub struct HttpServerTransport {
+    /// Address the listener is bound to.
+    local_addr: SocketAddr,
+    /// Receiver handed out through `receive()`.
+    incoming_rx: Arc<Mutex<mpsc::UnboundedReceiver<Result<Message, Error>>>>,
+    /// `POST` requests waiting for the server's response.
+    pending: PendingPosts,
+    /// Fan-out of server-initiated messages to open event streams.
+    events: broadcast::Sender<Message>,
+    /// Flipped to `true` on close to stop the listener and end event streams.
+    closed: watch::Sender<bool>,
+    /// Flipped to `true` once the client closes the session.
+    ended: Arc<watch::Sender<bool>>,
+}
+
+impl HttpServerTransport {
+    /// Binds an HTTP listener serving the MCP endpoint at `/mcp` on `addr`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the address cannot be bound.
+    pub async fn bind(addr: impl Into<SocketAddr>) -> Result<Self, Error> {
+        let addr: SocketAddr = addr.into();
+        let (incoming_tx, incoming_rx) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(100);
+        let (closed, closed_rx) = watch::channel(false);
+        let pending = Arc::new(Mutex::new(Vec::new()));
+        let ended = Arc::new(watch::channel(false).0);
+
+        let state = ServerState {
+            session: Arc::new(std::sync::Mutex::new(Session::Pending)),
+            ended: ended.clone(),
+            incoming_tx,
+            pending: pending.clone(),
+            events: events.clone(),
+            closed: closed_rx.clone(),
+        };
+        let with_state = warp::any().map(move || state.clone());
+
+        let session_id = warp::header::optional::<String>(SESSION_ID_HEADER);
+        let post = warp::post()
+            .and(warp::header::optional::<String>(PROTOCOL_VERSION_HEADER))
+            .and(session_id)
+            .and(warp::body::json::<serde_json::Value>())
+            .and(with_state.clone())
+            .and_then(handle_post);
+        let get = warp::get()
+            .and(session_id)
+            .and(with_state.clone())
+            .map(handle_get);
+        let delete = warp::delete()
+            .and(session_id)
+            .and(with_state)
+            .and_then(handle_delete);
+        let routes = warp::path(DEFAULT_ENDPOINT_PATH)
+            .and(warp::path::end())
+            .and(post.or(get).or(delete));
+
+        let mut shutdown = closed_rx;
+        let (local_addr, server) = warp::serve(routes)
+            .try_bind_with_graceful_shutdown(addr, async move {
+                let _ = shutdown.wait_for(|closed| *closed).await;
+            })
+            .map_err(|e| Error::Transport(e.to_string()))?;
+        tokio::spawn(server);
+        tracing::info!(%local_addr, "HTTP transport listening");
+
+        Ok(Self {
+            local_addr,
+            incoming_rx: Arc::new(Mutex::new(incoming_rx)),
+            pending,
+            events,
+            closed,
+            ended,
+        })
+    }
+
+    /// Returns the address the listener is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+/// Checks the `mcp-session-id` a client sent against the session, starting the
+/// session if `initialize` is set and none has started yet. Returns the current
+/// session id, or the status rejecting the request.
+fn check_session(
+    state: &ServerState,
+    session_id: Option<&str>,
+    initialize: bool,
+) -> Result<Option<String>, StatusCode> {
+    let mut session = state.session.lock().unwrap();
+    match (&*session, session_id) {
+        (Session::Pending, None) if initialize => {
+            let id = uuid::Uuid::new_v4().to_string();
+            tracing::debug!(session_id = %id, "Started HTTP session");
+            *session = Session::Active(id.clone());
+            Ok(Some(id))
+        }
+        (Session::Pending, None) => Ok(None),
+        (Session::Active(id), Some(sent)) if id == sent => Ok(Some(id.clone())),
+        (Session::Active(_), None) => Err(StatusCode::BAD_REQUEST),
+        _ => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+/// Replies to a request rejected by [`check_session`].
+fn reject_session(status: StatusCode) -> warp::reply::Response {
+    use warp::Reply;
+
+    let reason = if status == StatusCode::BAD_REQUEST {
+        format!("Missing {} header", SESSION_ID_HEADER)
+    } else {
+        "Unknown or closed session".to_string()
+    };
+    warp::reply::with_status(reason, status).into_response()
+}
+
+/// Attaches the session id, if a session has started, to a reply.
+fn with_session_id(reply: warp::reply::Response, session_id: Option<String>) -> warp::reply::Response {
+    use warp::Reply;
+
+    match session_id {
+        Some(id) => warp::reply::with_header(reply, SESSION_ID_HEADER, id).into_response(),
+        None => reply,
+    }
+}
+
+/// Handles a client `POST`: requests wait for their response, everything else is accepted.
+async fn handle_post(
+    protocol_version: Option<String>,
+    session_id: Option<String>,
+    body: serde_json::Value,
+    state: ServerState,
+) -> Result<warp::reply::Response, Infallible> {
+    use warp::Reply;
+
+    if let Some(version) = protocol_version {
+        if !crate::SUPPORTED_PROTOCOL_VERSIONS.contains(&version.as_str()) {
+            tracing::warn!(%version, "Rejecting unsupported MCP protocol version");
+            let reply = warp::reply::with_status(
+                format!("Unsupported MCP protocol version: {}", version),
+                StatusCode::BAD_REQUEST,
+            );
+            return Ok(reply.into_response());
+        }
+    }
+
+    let message = match serde_json::from_value::<Message>(body) {
+        Ok(message) => message,
+        Err(e) => {
+            tracing::warn!(error = %e, "Received invalid JSON-RPC message over HTTP");
+            let error = ResponseError {
+                code: ErrorCode::ParseError.into(),
+                message: e.to_string(),
+                data: None,
+            };
+            let reply = warp::reply::json(&Response::error(RequestId::Null, error));
+            return Ok(warp::reply::with_status(reply, StatusCode::BAD_REQUEST).into_response());
+        }
+    };
+
+    let initialize =
+        matches!(&message, Message::Request(request) if request.method == "initialize");
+    let session_id = match check_session(&state, session_id.as_deref(), initialize) {
+        Ok(session_id) => session_id,
+        Err(status) => return Ok(reject_session(status)),
+    };
+
+    // A request, or a batch containing requests, waits for the server's answer. So
+    // does a batch with malformed elements whose ids could be read, as the server
+    // answers those too.
+    let ids: Vec<RequestId> = match &message {
+        Message::Request(request) => vec![request.id.clone()],
+        Message::Batch(messages) => messages
+            .iter()
+            .filter_map(|m| match m {
+                Message::Request(request) => Some(request.id.clone()),
+                Message::Invalid(response) if response.id != RequestId::Null => {
+                    Some(response.id.clone())
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let reply = if ids.is_empty() {
+        let _ = state.incoming_tx.send(Ok(message));
+        StatusCode::ACCEPTED.into_response()
+    } else {
+        let rx = {
+            let mut pending = state.pending.lock().await;
+            let mut seen: HashSet<&RequestId> =
+                pending.iter().flat_map(|post| &post.ids).collect();
+            if let Some(id) = ids.iter().find(|id| !seen.insert(*id)) {
+                tracing::warn!(%id, "Rejecting request with an id already in flight");
+                let error = ResponseError {
+                    code: ErrorCode::InvalidRequest.into(),
+                    message: format!("Request id {} is already in use", id),
+                    data: None,
+                };
+                let reply = warp::reply::json(&Response::error(RequestId::Null, error));
+                return Ok(warp::reply::with_status(reply, StatusCode::CONFLICT).into_response());
+            }
+            let (tx, rx) = oneshot::channel();
+            pending.push(PendingPost { ids, reply: tx });
+            rx
+        };
+        let _ = state.incoming_tx.send(Ok(message));
+        match rx.await {
+            Ok(response) => warp::reply::json(&response).into_response(),
+            Err(_) => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+        }
+    };
+
+    Ok(with_session_id(reply, session_id))
+}
+
+/// Handles a client `GET` by opening an event stream of server-initiated messages.
+fn handle_get(session_id: Option<String>, state: ServerState) -> warp::reply::Response {
+    let session_id = match check_session(&state, session_id.as_deref(), false) {
+        Ok(session_id) => session_id,
+        Err(status) => return reject_session(status),
+    };
+
+    let mut events = state.events.subscribe();
+    let mut closed = state.closed;
+    let mut ended = state.ended.subscribe();
+    let stream = async_stream::stream! {
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(message) => match serde_json::to_string(&message) {
+                        Ok(data) => yield Ok::<_, Infallible>(warp::sse::Event::default().data(data)),
+                        Err(e) => tracing::error!(error = %e, "Failed to serialize event"),
+                    },
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "HTTP event stream lagged behind");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                // The flags only ever flip to `true`, so any change means closing.
+                _ = closed.changed() => break,
+                _ = ended.changed() => break,
+            }
+        }
+    };
+
+    let reply = warp::sse::reply(warp::sse::keep_alive().stream(stream));
+    with_session_id(warp::reply::Reply::into_response(reply), session_id)
+}
+
+/// Handles a client `DELETE` by closing the session, which ends the receive stream.
+async fn handle_delete(
+    session_id: Option<String>,
+    state: ServerState,
+) -> Result<warp::reply::Response, Infallible> {
+    use warp::Reply;
+
+    match check_session(&state, session_id.as_deref(), false) {
+        Ok(Some(id)) => {
+            tracing::debug!(session_id = %id, "HTTP session closed by the client");
+            *state.session.lock().unwrap() = Session::Closed;
+            let _ = state.ended.send(true);
+            // Waiting `POST`s get no answer from a closed session.
+            state.pending.lock().await.clear();
+            Ok(StatusCode::OK.into_response())
+        }
+        Ok(None) => Ok(reject_session(StatusCode::BAD_REQUEST)),
+        Err(status) => Ok(reject_session(status)),
+    }
+}
+
+#[async_trait]
+impl Transport for HttpServerTransport {
+    /// Sends a response back on its originating `POST`, or any other message over
+    /// the event stream.
+    async fn send(&self, message: Message) -> Result<(), Error> {
+        let response_id = match &message {
+            Message::Response(response) => Some(&response.id),
+            Message::Batch(messages) => messages.iter().find_map(|m| match m {
+                Message::Response(response) => Some(&response.id),
+                _ => None,
+            }),
+            _ => None,
+        };
+
+        let waiting = match response_id {
+            Some(id) => {
+                let mut pending = self.pending.lock().await;
+                pending
+                    .iter()
+                    .position(|post| post.ids.contains(id))
+                    .map(|index| pending.swap_remove(index))
+            }
+            None => None,
+        };
+        if let Some(post) = waiting {
+            return post
+                .reply
+                .send(message)
+                .map_err(|_| Error::Transport("HTTP request was dropped".to_string()));
+        }
+
+        self.events
+            .send(message)
+            .map(|_| ())
+            .map_err(|_| Error::Transport("No HTTP event stream is connected".to_string()))
+    }
+
+    /// Provides a stream of messages posted by the client, ending once the client
+    /// closes the session.
+    fn receive(&self) -> Pin<Box<dyn Stream<Item = Result<Message, Error>> + Send>> {
+        let rx = self.incoming_rx.clone();
+        let mut ended = self.ended.subscribe();
+        Box::pin(async_stream::stream! {
+            let mut rx = rx.lock().await;
+            loop {
+                let msg = tokio::select! {
+                    // Messages posted before the session closed are still delivered.
+                    biased;
+                    msg = rx.recv() => msg,
+                    _ = ended.wait_for(|ended| *ended) => None,
+                };
+                match msg {
+                    Some(msg) => yield msg,
+                    None => break,
+                }
+            }
+        })
+    }
+
+    /// Shuts the listener down and ends all open event streams.
+    async fn close(&self) -> Result<(), Error> {
+        let _ = self.closed.send(true);
+        self.pending.lock().await.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Notification, Request};
+    use std::time::Duration;
+
+    async fn connect() -> (HttpServerTransport, HttpClientTransport) {
+        let server = HttpServerTransport::bind(([127, 0, 0, 1], 0)).await.unwrap();
+        let client = HttpClientTransport::new(format!(
+            "http://{}/{}",
+            server.local_addr(),
+            DEFAULT_ENDPOINT_PATH
+        ));
+        (server, client)
+    }
+
+    #[tokio::test]
+    async fn test_request_response_roundtrip() {
+        let (server, client) = connect().await;
+        let mut server_rx = server.receive();
+        let mut client_rx = client.receive();
+
+        let responder = tokio::spawn(async move {
+            match server_rx.next().await {
+                Some(Ok(Message::Request(request))) => {
+                    assert_eq!(request.method, "initialize");
+                    server
+                        .send(Message::Response(Response::success(
+                            request.id,
+                            Some(serde_json::json!({})),
+                        )))
+                        .await
+                        .unwrap();
+                }
+                other => panic!("Expected request, got {:?}", other),
+            }
+            server
+        });
+
+        client
+            .send(Message::Request(Request::new("initialize", None, RequestId::Number(7))))
+            .await
+            .unwrap();
+
+        let received = tokio::time::timeout(Duration::from_secs(5), client_rx.next())
+            .await
+            .expect("Response should arrive before timeout");
+        match received {
+            Some(Ok(Message::Response(response))) => {
+                assert_eq!(response.id, RequestId::Number(7));
+                assert!(response.error.is_none());
+            }
+            other => panic!("Expected response, got {:?}", other),
+        }
+        assert!(client.session_id().await.is_some());
+
+        let server = responder.await.unwrap();
+        server.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_server_notification_over_event_stream() {
+        let (server, client) = connect().await;
+        let mut server_rx = server.receive();
+        let mut client_rx = client.receive();
+
+        // Any POST opens the event stream.
+        client
+            .send(Message::Notification(Notification::new(
+                "notifications/initialized",
+                None,
+            )))
+            .await
+            .unwrap();
+        assert!(matches!(
+            server_rx.next().await,
+            Some(Ok(Message::Notification(_)))
+        ));
+
+        // The GET stream connects asynchronously; retry until it is subscribed.
+        let notification = Notification::new("notifications/tools/list_changed", None);
+        let mut delivered = false;
+        for _ in 0..50 {
+            if server
+                .send(Message::Notification(notification.clone()))
+                .await
+                .is_ok()
+            {
+                delivered = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(delivered, "Event stream should connect");
+
+        let received = tokio::time::timeout(Duration::from_secs(5), client_rx.next())
+            .await
+            .expect("Notification should arrive before timeout");
+        match received {
+            Some(Ok(Message::Notification(notif))) => {
+                assert_eq!(notif.method, "notifications/tools/list_changed");
+            }
+            other => panic!("Expected notification, got {:?}", other),
+        }
+
+        client.close().await.unwrap();
+        server.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_invalid_message_answered_with_null_id() {
+        let (server, _client) = connect().await;
+
+        let response = reqwest::Client::new()
+            .post(format!("http://{}/{}", server.local_addr(), DEFAULT_ENDPOINT_PATH))
+            .json(&serde_json::json!({"jsonrpc": "2.0", "id": 3}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["id"], serde_json::Value::Null);
+        assert_eq!(body["error"]["code"], i32::from(ErrorCode::ParseError));
+
+        server.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_batch_post_answered_and_duplicate_ids_rejected() {
+        let (server, _client) = connect().await;
+        let mut server_rx = server.receive();
+        let url = format!("http://{}/{}", server.local_addr(), DEFAULT_ENDPOINT_PATH);
+        let post = |body: serde_json::Value| reqwest::Client::new().post(&url).json(&body).send();
+
+        let batch = tokio::spawn(post(serde_json::json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "ping"},
+            {"jsonrpc": "2.0", "id": 2, "method": "ping"}
+        ])));
+        assert!(matches!(server_rx.next().await, Some(Ok(Message::Batch(_)))));
+
+        // Id 2 is in flight as part of the batch, and ids within a batch must differ.
+        for body in [
+            serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "ping"}),
+            serde_json::json!([
+                {"jsonrpc": "2.0", "id": 3, "method": "ping"},
+                {"jsonrpc": "2.0", "id": 3, "method": "ping"}
+            ]),
+        ] {
+            let response = post(body).await.unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::CONFLICT);
+        }
+
+        // The batch answer goes back on the batch's POST, whatever its order.
+        let responses = [2, 1]
+            .map(|id| Message::Response(Response::success(RequestId::Number(id), None)))
+            .to_vec();
+        server.send(Message::Batch(responses)).await.unwrap();
+        let body: serde_json::Value = batch.await.unwrap().unwrap().json().await.unwrap();
+        assert_eq!(body[0]["id"], 2);
+        assert_eq!(body[1]["id"], 1);
+
+        server.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_session_id_checked_until_delete() {
+        let (server, _client) = connect().await;
+        let mut server_rx = server.receive();
+        let url = format!("http://{}/{}", server.local_addr(), DEFAULT_ENDPOINT_PATH);
+        let http = reqwest::Client::new();
+        let initialized = serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+
+        let initialize = tokio::spawn(
+            http.post(&url)
+                .json(&serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "initialize"}))
+                .send(),
+        );
+        assert!(matches!(server_rx.next().await, Some(Ok(Message::Request(_)))));
+        server
+            .send(Message::Response(Response::success(RequestId::Number(1), None)))
+            .await
+            .unwrap();
+        let response = initialize.await.unwrap().unwrap();
+        let session_id = response.headers()[SESSION_ID_HEADER].to_str().unwrap().to_string();
+
+        let post = |session_id: Option<&str>| {
+            let mut request = http.post(&url).json(&initialized);
+            if let Some(id) = session_id {
+                request = request.header(SESSION_ID_HEADER, id);
+            }
+            request.send()
+        };
+        assert_eq!(post(None).await.unwrap().status(), reqwest::StatusCode::BAD_REQUEST);
+        assert_eq!(post(Some("unknown")).await.unwrap().status(), reqwest::StatusCode::NOT_FOUND);
+        assert_eq!(post(Some(&session_id)).await.unwrap().status(), reqwest::StatusCode::ACCEPTED);
+        assert!(matches!(server_rx.next().await, Some(Ok(Message::Notification(_)))));
+        let get = http.get(&url).header(SESSION_ID_HEADER, "unknown").send().await.unwrap();
+        assert_eq!(get.status(), reqwest::StatusCode::NOT_FOUND);
+
+        // Deleting the session ends the receive stream and retires the id.
+        let delete = http.delete(&url).header(SESSION_ID_HEADER, &session_id).send().await.unwrap();
+        assert_eq!(delete.status(), reqwest::StatusCode::OK);
+        let next = tokio::time::timeout(Duration::from_secs(5), server_rx.next()).await;
+        assert!(matches!(next, Ok(None)));
+        assert_eq!(post(Some(&session_id)).await.unwrap().status(), reqwest::StatusCode::NOT_FOUND);
+
+        server.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_protocol_version_header_rejected() {
+        let (server, client) = connect().await;
+
+        client.set_protocol_version("1999-01-01");
+        let result = client
+            .send(Message::Notification(Notification::new("notifications/initialized", None)))
+            .await;
+        assert!(matches!(result, Err(Error::Transport(msg)) if msg.contains("400")));
+
+        client.set_protocol_version(crate::LATEST_PROTOCOL_VERSION);
+        client
+            .send(Message::Notification(Notification::new("notifications/initialized", None)))
+            .await
+            .unwrap();
+
+        server.close().await.unwrap();
+    }
+}
@@ -0,0 +1,128 @@
+//! In-memory transport.
+//!
+//! [`duplex`] returns two connected endpoints, so a `Client` and a `Server` can talk
+//! to each other inside the same process without pipes or sockets.
+
+use async_trait::async_trait;
+use futures::Stream;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::{
+    error::Error,
+    transport::{Message, Transport},
+};
+
+/// One end of an in-memory connection created by [`duplex`].
+pub struct MemoryTransport {
+    /// Sender delivering messages to the peer; taken on close so the peer sees EOF.
+    sender: Mutex<Option<mpsc::UnboundedSender<Result<Message, Error>>>>,
+    /// Receiver for messages sent by the peer.
+    receiver: Arc<Mutex<mpsc::UnboundedReceiver<Result<Message, Error>>>>,
+}
+
+/// Creates a pair of connected transports: whatever is sent on one is received on the other.
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use alou::client::Client;
+/// use alou::transport::memory::duplex;
+///
+/// let (client_side, server_side) = duplex();
+/// let client = Client::new(Arc::new(client_side), None);
+/// // Hand `server_side` to `Server::new(...)` and run `server.start()` in a task.
+/// ```
+pub fn duplex() -> (MemoryTransport, MemoryTransport) {
+    let (a_tx, a_rx) = mpsc::unbounded_channel();
+    let (b_tx, b_rx) = mpsc::unbounded_channel();
+    (MemoryTransport::new(a_tx, b_rx), MemoryTransport::new(b_tx, a_rx))
+}
+
+impl MemoryTransport {
+    fn new(
+        sender: mpsc::UnboundedSender<Result<Message, Error>>,
+        receiver: mpsc::UnboundedReceiver<Result<Message, Error>>,
+    ) -> Self {
+        Self {
+            sender: Mutex::new(Some(sender)),
+            receiver: Arc::new(Mutex::new(receiver)),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for MemoryTransport {
+    /// Delivers a message to the peer endpoint.
+    async fn send(&self, message: Message) -> Result<(), Error> {
+        let sender = self.sender.lock().await;
+        let sender = sender
+            .as_ref()
+            .ok_or_else(|| Error::Transport("Transport is closed".to_string()))?;
+        sender
+            .send(Ok(message))
+            .map_err(|_| Error::Transport("Peer transport was dropped".to_string()))
+    }
+
+    /// Provides a stream of messages sent by the peer endpoint.
+    fn receive(&self) -> Pin<Box<dyn Stream<Item = Result<Message, Error>> + Send>> {
+        let rx = self.receiver.clone();
+        Box::pin(async_stream::stream! {
+            let mut rx = rx.lock().await;
+            while let Some(msg) = rx.recv().await {
+                yield msg;
+            }
+        })
+    }
+
+    /// Closes this end; the peer's receive stream ends once pending messages are drained.
+    async fn close(&self) -> Result<(), Error> {
+        self.sender.lock().await.take();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Notification, Request, RequestId};
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_duplex_delivers_both_ways() {
+        let (a, b) = duplex();
+        let mut a_rx = a.receive();
+        let mut b_rx = b.receive();
+
+        a.send(Message::Request(Request::new("ping", None, RequestId::Number(1))))
+            .await
+            .unwrap();
+        b.send(Message::Notification(Notification::new("notifications/initialized", None)))
+            .await
+            .unwrap();
+
+        assert!(matches!(b_rx.next().await, Some(Ok(Message::Request(r))) if r.method == "ping"));
+        assert!(matches!(
+            a_rx.next().await,
+            Some(Ok(Message::Notification(n))) if n.method == "notifications/initialized"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_close_ends_peer_stream() {
+        let (a, b) = duplex();
+        let mut b_rx = b.receive();
+
+        a.send(Message::Notification(Notification::new("last", None)))
+            .await
+            .unwrap();
+        a.close().await.unwrap();
+
+        assert!(matches!(b_rx.next().await, Some(Ok(Message::Notification(_)))));
+        assert!(b_rx.next().await.is_none());
+        assert!(a
+            .send(Message::Notification(Notification::new("after-close", None)))
+            .await
+            .is_err());
+    }
+}
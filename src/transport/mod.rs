@@ -1,13 +1,14 @@
 use async_trait::async_trait;
 use futures::Stream;
-use serde::de::{self, MapAccess, Visitor};
-use serde::ser::SerializeMap;
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::pin::Pin;
 
 use crate::Error;
-use crate::protocol::{Notification, Request, Response};
+use crate::error::ErrorCode;
+use crate::protocol::{Notification, Request, RequestId, Response, ResponseError};
 
 /// A message that can be sent over a transport
 #[derive(Debug, Clone)]
@@ -15,6 +16,11 @@ pub enum Message {
     Request(Request),
     Response(Response),
     Notification(Notification),
+    /// A JSON-RPC 2.0 batch: an array of requests/notifications, or of responses.
+    Batch(Vec<Message>),
+    /// An element of a batch that is not a valid message, holding the
+    /// `InvalidRequest` error response to send back for it.
+    Invalid(Response),
 }
 
 impl Message {
@@ -24,6 +30,8 @@ impl Message {
             Message::Request(_) => "request",
             Message::Response(_) => "response",
             Message::Notification(_) => "notification",
+            Message::Batch(_) => "batch",
+            Message::Invalid(_) => "invalid",
         }
     }
 }
@@ -35,7 +43,32 @@ impl<'de> Visitor<'de> for MessageVisitor {
     type Value = Message;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("a valid JSON-RPC 2.0 message")
+        formatter.write_str("a valid JSON-RPC 2.0 message or batch")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        tracing::debug!("Attempting to deserialize batch...");
+
+        // A bad element only invalidates itself, so the rest of the batch is still handled.
+        let mut messages = Vec::new();
+        while let Some(value) = seq.next_element::<serde_json::Value>()? {
+            let message = match value {
+                serde_json::Value::Array(_) => {
+                    invalid_element(&value, "nested batches are not allowed".to_string())
+                }
+                value => serde_json::from_value::<Message>(value.clone())
+                    .unwrap_or_else(|e| invalid_element(&value, e.to_string())),
+            };
+            messages.push(message);
+        }
+
+        if messages.is_empty() {
+            return Err(de::Error::custom("invalid batch: empty array"));
+        }
+        Ok(Message::Batch(messages))
     }
 
     fn visit_map<M>(self, mut map: M) -> Result<Self::Value, M::Error>
@@ -56,10 +89,13 @@ impl<'de> Visitor<'de> for MessageVisitor {
         let value = serde_json::Value::Object(obj);
 
         // Determine message type based on JSON-RPC 2.0 spec
-        if let Some(_id_val) = value.get("id") {
+        if let Some(id) = value.get("id") {
             // If `id` is present, it must be a valid string or number for request/response
             if value.get("method").is_some() {
                 // Request must have `method` and `id`
+                if id.is_null() {
+                    return Err(de::Error::custom("invalid request: 'id' must not be null"));
+                }
                 tracing::debug!("Deserializing as request...");
                 tracing::debug!("Value: {:?}", value);
                 Ok(Message::Request(
@@ -94,12 +130,28 @@ impl<'de> Visitor<'de> for MessageVisitor {
     }
 }
 
+/// Turns a batch element that failed to parse into the error response owed for it,
+/// addressed to its id if one can be read.
+fn invalid_element(value: &serde_json::Value, reason: String) -> Message {
+    tracing::warn!(%reason, "Invalid element in batch");
+    let id = value
+        .get("id")
+        .and_then(|id| serde_json::from_value::<RequestId>(id.clone()).ok())
+        .unwrap_or(RequestId::Null);
+    let error = ResponseError {
+        code: ErrorCode::InvalidRequest.into(),
+        message: format!("Invalid request: {}", reason),
+        data: None,
+    };
+    Message::Invalid(Response::error(id, error))
+}
+
 impl<'de> Deserialize<'de> for Message {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_map(MessageVisitor)
+        deserializer.deserialize_any(MessageVisitor)
     }
 }
 
@@ -108,6 +160,14 @@ impl Serialize for Message {
     where
         S: serde::Serializer,
     {
+        if let Message::Batch(messages) = self {
+            let mut seq = serializer.serialize_seq(Some(messages.len()))?;
+            for message in messages {
+                seq.serialize_element(message)?;
+            }
+            return seq.end();
+        }
+
         let mut map = serializer.serialize_map(None)?;
 
         // Always add type field for our protocol
@@ -123,7 +183,7 @@ impl Serialize for Message {
                 }
                 map.serialize_entry("id", &req.id)?;
             }
-            Message::Response(resp) => {
+            Message::Response(resp) | Message::Invalid(resp) => {
                 map.serialize_entry("jsonrpc", &resp.jsonrpc)?;
                 map.serialize_entry("id", &resp.id)?;
                 if let Some(ref result) = resp.result {
@@ -140,6 +200,7 @@ impl Serialize for Message {
                     map.serialize_entry("params", params)?;
                 }
             }
+            Message::Batch(_) => unreachable!("batches are serialized as sequences above"),
         }
 
         map.end()
@@ -157,6 +218,103 @@ pub trait Transport: Send + Sync + 'static {
 
     /// Close the transport
     async fn close(&self) -> Result<(), Error>;
+
+    /// Records the protocol version negotiated during initialization, for transports
+    /// that must announce it on every exchange (e.g. as an HTTP header).
+    fn set_protocol_version(&self, _version: &str) {}
 }
 
+pub mod http;
+pub mod memory;
+pub mod socket;
 pub mod stdio;
+pub mod websocket;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_batch_roundtrip() {
+        let batch = Message::Batch(vec![
+            Message::Request(Request::new("tools/list", None, RequestId::Number(1))),
+            Message::Notification(Notification::new("notifications/initialized", None)),
+        ]);
+
+        let value = serde_json::to_value(&batch).unwrap();
+        assert_eq!(
+            value,
+            json!([
+                {"jsonrpc": "2.0", "method": "tools/list", "id": 1},
+                {"jsonrpc": "2.0", "method": "notifications/initialized"}
+            ])
+        );
+
+        match serde_json::from_value::<Message>(value).unwrap() {
+            Message::Batch(messages) => {
+                assert_eq!(messages.len(), 2);
+                assert!(matches!(messages[0], Message::Request(_)));
+                assert!(matches!(messages[1], Message::Notification(_)));
+            }
+            other => panic!("Expected batch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_batch_of_responses_from_str() {
+        let raw = r#"[{"jsonrpc":"2.0","id":1,"result":{}},{"jsonrpc":"2.0","id":2,"error":{"code":-32601,"message":"Method not found"}}]"#;
+        match serde_json::from_str::<Message>(raw).unwrap() {
+            Message::Batch(messages) => {
+                assert!(matches!(&messages[0], Message::Response(r) if r.result.is_some()));
+                assert!(matches!(&messages[1], Message::Response(r) if r.error.is_some()));
+            }
+            other => panic!("Expected batch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_batches_rejected() {
+        assert!(serde_json::from_str::<Message>("[]").is_err());
+        assert!(serde_json::from_str::<Message>("42").is_err());
+    }
+
+    #[test]
+    fn test_invalid_batch_elements_answered_individually() {
+        let raw = r#"[
+            {"jsonrpc":"2.0","id":1,"method":"ping"},
+            {"jsonrpc":"2.0","id":2},
+            [{"jsonrpc":"2.0","method":"a"}],
+            42
+        ]"#;
+        match serde_json::from_str::<Message>(raw).unwrap() {
+            Message::Batch(messages) => {
+                assert_eq!(messages.len(), 4);
+                assert!(matches!(&messages[0], Message::Request(_)));
+                let ids: Vec<_> = messages[1..]
+                    .iter()
+                    .map(|message| match message {
+                        Message::Invalid(response) => {
+                            let code = response.error.as_ref().unwrap().code;
+                            assert_eq!(code, i32::from(ErrorCode::InvalidRequest));
+                            response.id.clone()
+                        }
+                        other => panic!("Expected invalid element, got {:?}", other),
+                    })
+                    .collect();
+                assert_eq!(ids, [RequestId::Number(2), RequestId::Null, RequestId::Null]);
+            }
+            other => panic!("Expected batch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_null_id_only_allowed_in_responses() {
+        let raw = r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"Parse error"}}"#;
+        match serde_json::from_str::<Message>(raw).unwrap() {
+            Message::Response(response) => assert_eq!(response.id, RequestId::Null),
+            other => panic!("Expected response, got {:?}", other),
+        }
+        assert!(serde_json::from_str::<Message>(r#"{"jsonrpc":"2.0","id":null,"method":"ping"}"#).is_err());
+    }
+}
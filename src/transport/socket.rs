@@ -0,0 +1,125 @@
+//! Socket transports.
+//!
+//! TCP and Unix domain socket connections use the same newline-delimited JSON framing
+//! as [`StdioTransport`], so a long-lived MCP daemon can serve many clients without a
+//! process being spawned for each of them.
+
+use tokio::net::{tcp, TcpStream, ToSocketAddrs};
+
+use crate::{error::Error, transport::stdio::StdioTransport};
+
+/// Newline-delimited JSON over a TCP connection.
+pub type TcpTransport = StdioTransport<tcp::OwnedWriteHalf>;
+
+impl StdioTransport<tcp::OwnedWriteHalf> {
+    /// Connects to an MCP server listening on `addr`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the connection cannot be established.
+    pub async fn connect(addr: impl ToSocketAddrs) -> Result<Self, Error> {
+        let stream = TcpStream::connect(addr).await?;
+        Self::from_stream(stream)
+    }
+
+    /// Wraps an already established connection, e.g. one accepted by a `TcpListener`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if transport creation fails.
+    pub fn from_stream(stream: TcpStream) -> Result<Self, Error> {
+        stream.set_nodelay(true)?;
+        let (read, write) = stream.into_split();
+        Self::with_streams(read, write)
+    }
+}
+
+/// Newline-delimited JSON over a Unix domain socket.
+#[cfg(unix)]
+pub type UnixTransport = StdioTransport<tokio::net::unix::OwnedWriteHalf>;
+
+#[cfg(unix)]
+impl StdioTransport<tokio::net::unix::OwnedWriteHalf> {
+    /// Connects to an MCP server listening on the socket at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the connection cannot be established.
+    pub async fn connect(path: impl AsRef<std::path::Path>) -> Result<Self, Error> {
+        let stream = tokio::net::UnixStream::connect(path).await?;
+        Self::from_stream(stream)
+    }
+
+    /// Wraps an already established connection, e.g. one accepted by a `UnixListener`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if transport creation fails.
+    pub fn from_stream(stream: tokio::net::UnixStream) -> Result<Self, Error> {
+        let (read, write) = stream.into_split();
+        Self::with_streams(read, write)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Request, RequestId, Response};
+    use crate::transport::{Message, Transport};
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    async fn roundtrip(client: &dyn Transport, server: &dyn Transport) {
+        let mut client_rx = client.receive();
+        let mut server_rx = server.receive();
+
+        client
+            .send(Message::Request(Request::new("ping", None, RequestId::Number(1))))
+            .await
+            .unwrap();
+        match tokio::time::timeout(Duration::from_secs(5), server_rx.next()).await {
+            Ok(Some(Ok(Message::Request(request)))) => {
+                server
+                    .send(Message::Response(Response::success(
+                        request.id,
+                        Some(serde_json::json!({})),
+                    )))
+                    .await
+                    .unwrap();
+            }
+            other => panic!("Expected request, got {:?}", other),
+        }
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => {
+                assert_eq!(response.id, RequestId::Number(1));
+            }
+            other => panic!("Expected response, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tcp_roundtrip() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = TcpTransport::connect(addr).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        let server = TcpTransport::from_stream(stream).unwrap();
+
+        roundtrip(&client, &server).await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_roundtrip() {
+        let path = std::env::temp_dir().join(format!("alou-{}.sock", uuid::Uuid::new_v4()));
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+        let client = UnixTransport::connect(&path).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        let server = UnixTransport::from_stream(stream).unwrap();
+
+        roundtrip(&client, &server).await;
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,9 +1,10 @@
 use async_trait::async_trait;
 use futures::Stream;
 use std::pin::Pin;
+use std::sync::Arc;
 use tokio::{
     io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
-    sync::broadcast,
+    sync::{mpsc, Mutex},
 };
 
 use crate::{
@@ -11,14 +12,21 @@ use crate::{
     transport::{Message, Transport},
 };
 
+/// Number of parsed messages buffered ahead of the consumer. Once full, the reader
+/// task stops reading until the consumer catches up, so nothing is ever dropped.
+const INCOMING_BUFFER_SIZE: usize = 100;
+
 /// A transport that uses provided async read/write streams for MCP communication.
+///
+/// Incoming messages are delivered through a bounded channel to a single consumer:
+/// a slow consumer applies backpressure to the underlying stream instead of losing
+/// messages. Streams returned by repeated `receive()` calls share that consumer, so
+/// each message is yielded exactly once.
 pub struct StdioTransport<W> {
     /// A mutex-protected writer for sending messages.
     writer: tokio::sync::Mutex<W>,
-    /// A broadcast receiver for incoming messages read from the stream.
-    receiver: broadcast::Receiver<Result<Message, Error>>,
-    // Keep sender in scope to avoid dropping.
-    _sender: broadcast::Sender<Result<Message, Error>>,
+    /// A receiver for incoming messages read from the stream.
+    receiver: Arc<Mutex<mpsc::Receiver<Result<Message, Error>>>>,
 }
 
 impl<W> StdioTransport<W>
@@ -34,10 +42,9 @@ where
     where
         R: AsyncRead + Unpin + Send + 'static,
     {
-        let (sender, receiver) = broadcast::channel(100);
+        let (sender, receiver) = mpsc::channel(INCOMING_BUFFER_SIZE);
         let writer = tokio::sync::Mutex::new(write);
 
-        let sender_clone = sender.clone();
         tokio::spawn(async move {
             let mut reader = BufReader::new(read);
             let mut line = String::new();
@@ -49,7 +56,7 @@ where
                         tracing::debug!(
                             "EOF reached, send an EOF error so the stream ends gracefully"
                         );
-                        let _ = sender_clone.send(Err(Error::Other("EOF".to_string())));
+                        let _ = sender.send(Err(Error::Other("EOF".to_string()))).await;
                         break;
                     }
                     Ok(_) => {
@@ -61,7 +68,9 @@ where
                             Ok(m) => Ok(m),
                             Err(err) => {
                                 // 检查是否是JSON-RPC消息格式
-                                if trimmed.starts_with('{') && trimmed.ends_with('}') {
+                                if (trimmed.starts_with('{') && trimmed.ends_with('}'))
+                                    || (trimmed.starts_with('[') && trimmed.ends_with(']'))
+                                {
                                     tracing::warn!("Failed to parse JSON message: {} - Raw content: {}", err, trimmed);
                                     Err(Error::Serialization(format!("{} - Raw: {}", err, trimmed)))
                                 } else {
@@ -72,10 +81,14 @@ where
                             },
                         };
 
-                        let _ = sender_clone.send(message);
+                        // Waits for buffer space rather than dropping the message.
+                        if sender.send(message).await.is_err() {
+                            tracing::debug!("Receiver dropped, stop reading");
+                            break;
+                        }
                     }
                     Err(err) => {
-                        let _ = sender_clone.send(Err(Error::Io(err.to_string())));
+                        let _ = sender.send(Err(Error::Io(err.to_string()))).await;
                         break;
                     }
                 }
@@ -84,8 +97,7 @@ where
 
         Ok(StdioTransport {
             writer,
-            receiver,
-            _sender: sender,
+            receiver: Arc::new(Mutex::new(receiver)),
         })
     }
 }
@@ -114,12 +126,10 @@ where
 
     /// Provides a stream of incoming messages read from the stdin or other input stream.
     fn receive(&self) -> Pin<Box<dyn Stream<Item = Result<Message, Error>> + Send>> {
-        let rx = self.receiver.resubscribe();
-        Box::pin(futures::stream::unfold(rx, |mut rx| async move {
-            match rx.recv().await {
-                Ok(msg) => Some((msg, rx)),
-                Err(_) => None,
-            }
+        let rx = self.receiver.clone();
+        Box::pin(futures::stream::unfold(rx, |rx| async move {
+            let msg = rx.lock().await.recv().await?;
+            Some((msg, rx))
         }))
     }
 
@@ -128,3 +138,94 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Notification, RequestId, Response};
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_flood_with_slow_consumer_loses_nothing() {
+        const COUNT: i64 = 5_000;
+        let (mut peer, local) = tokio::io::duplex(64 * 1024);
+        let (read, write) = tokio::io::split(local);
+        let transport = StdioTransport::with_streams(read, write).unwrap();
+
+        // Write far more messages than the buffer can hold, as fast as possible.
+        let writer = tokio::spawn(async move {
+            for i in 0..COUNT {
+                let message = if i % 2 == 0 {
+                    Message::Notification(Notification::new(
+                        "notifications/progress",
+                        Some(serde_json::json!({"progress": i})),
+                    ))
+                } else {
+                    Message::Response(Response::success(
+                        RequestId::Number(i),
+                        Some(serde_json::json!({"i": i})),
+                    ))
+                };
+                let line = format!("{}\n", serde_json::to_string(&message).unwrap());
+                peer.write_all(line.as_bytes()).await.unwrap();
+            }
+            peer
+        });
+
+        let mut stream = transport.receive();
+        for i in 0..COUNT {
+            if i % 1_000 == 0 {
+                // Simulate a consumer that periodically falls behind.
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            match stream.next().await {
+                Some(Ok(Message::Notification(notif))) => {
+                    assert_eq!(i % 2, 0);
+                    assert_eq!(notif.params, Some(serde_json::json!({"progress": i})));
+                }
+                Some(Ok(Message::Response(response))) => {
+                    assert_eq!(response.id, RequestId::Number(i));
+                }
+                other => panic!("Unexpected item at {}: {:?}", i, other),
+            }
+        }
+        drop(writer.await.unwrap());
+        assert!(matches!(stream.next().await, Some(Err(Error::Other(_)))));
+    }
+
+    #[tokio::test]
+    async fn test_large_messages_survive_restarted_receive() {
+        let (mut peer, local) = tokio::io::duplex(1024);
+        let (read, write) = tokio::io::split(local);
+        let transport = StdioTransport::with_streams(read, write).unwrap();
+
+        let big = "x".repeat(1024 * 1024);
+        let payload = big.clone();
+        tokio::spawn(async move {
+            for i in 0..3 {
+                let message = Message::Response(Response::success(
+                    RequestId::Number(i),
+                    Some(serde_json::json!({"text": payload})),
+                ));
+                let line = format!("{}\n", serde_json::to_string(&message).unwrap());
+                peer.write_all(line.as_bytes()).await.unwrap();
+            }
+            // Keep the stream open until the test is done.
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        // A consumer that drops its stream and calls `receive()` again must continue
+        // where the previous one stopped.
+        for i in 0..3 {
+            let mut stream = transport.receive();
+            match stream.next().await {
+                Some(Ok(Message::Response(response))) => {
+                    assert_eq!(response.id, RequestId::Number(i));
+                    assert_eq!(response.result, Some(serde_json::json!({"text": big})));
+                }
+                other => panic!("Unexpected item: {:?}", other),
+            }
+        }
+    }
+}
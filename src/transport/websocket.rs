@@ -0,0 +1,332 @@
+//! WebSocket transport.
+//!
+//! Every JSON-RPC message travels as a single text frame over one persistent,
+//! full-duplex connection. [`WebSocketTransport::connect`] dials a remote endpoint
+//! for use with `Client`, while [`WebSocketListener`] accepts incoming connections
+//! that can each be handed to a `Server`.
+
+use async_trait::async_trait;
+use futures::{SinkExt, Stream, StreamExt};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::{mpsc, watch, Mutex};
+use tokio_tungstenite::tungstenite;
+use warp::Filter;
+
+use crate::{
+    error::Error,
+    transport::{http::DEFAULT_ENDPOINT_PATH, Message, Transport},
+};
+
+/// Frames queued for the writer task.
+enum Outgoing {
+    Text(String),
+    Close,
+}
+
+/// A transport exchanging JSON-RPC messages as WebSocket text frames.
+pub struct WebSocketTransport {
+    /// Queue of frames for the writer task.
+    outgoing: mpsc::UnboundedSender<Outgoing>,
+    /// Receiver handed out through `receive()`.
+    incoming_rx: Arc<Mutex<mpsc::UnboundedReceiver<Result<Message, Error>>>>,
+}
+
+impl WebSocketTransport {
+    /// Connects to a WebSocket endpoint such as `ws://localhost:8080/mcp`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the connection or the WebSocket handshake fails.
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        tracing::debug!(%url, "Connecting WebSocket transport");
+        let (socket, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(|e| Error::Transport(e.to_string()))?;
+        let (mut sink, mut stream) = socket.split();
+
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(frame) = outgoing_rx.recv().await {
+                let result = match frame {
+                    Outgoing::Text(text) => sink.send(tungstenite::Message::Text(text)).await,
+                    Outgoing::Close => {
+                        let _ = sink.send(tungstenite::Message::Close(None)).await;
+                        break;
+                    }
+                };
+                if let Err(e) = result {
+                    tracing::warn!(error = %e, "Failed to write WebSocket frame");
+                    break;
+                }
+            }
+        });
+
+        let (incoming_tx, incoming_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(frame) = stream.next().await {
+                let text = match frame {
+                    Ok(tungstenite::Message::Text(text)) => text,
+                    Ok(tungstenite::Message::Binary(bytes)) => {
+                        String::from_utf8_lossy(&bytes).into_owned()
+                    }
+                    Ok(tungstenite::Message::Close(_)) => break,
+                    Ok(_) => continue,
+                    Err(e) => {
+                        let _ = incoming_tx.send(Err(Error::Transport(e.to_string())));
+                        break;
+                    }
+                };
+                if forward_text(&text, &incoming_tx).is_err() {
+                    break;
+                }
+            }
+            tracing::debug!("WebSocket reader task terminated");
+        });
+
+        Ok(Self {
+            outgoing,
+            incoming_rx: Arc::new(Mutex::new(incoming_rx)),
+        })
+    }
+
+    /// Wraps a WebSocket accepted through a `warp::ws()` filter, so an MCP endpoint
+    /// can be mounted inside an existing warp application.
+    pub fn from_warp(socket: warp::ws::WebSocket) -> Self {
+        let (mut sink, mut stream) = socket.split();
+
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(frame) = outgoing_rx.recv().await {
+                let result = match frame {
+                    Outgoing::Text(text) => sink.send(warp::ws::Message::text(text)).await,
+                    Outgoing::Close => {
+                        let _ = sink.send(warp::ws::Message::close()).await;
+                        break;
+                    }
+                };
+                if let Err(e) = result {
+                    tracing::warn!(error = %e, "Failed to write WebSocket frame");
+                    break;
+                }
+            }
+        });
+
+        let (incoming_tx, incoming_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(frame) = stream.next().await {
+                let frame = match frame {
+                    Ok(frame) => frame,
+                    Err(e) => {
+                        let _ = incoming_tx.send(Err(Error::Transport(e.to_string())));
+                        break;
+                    }
+                };
+                if frame.is_close() {
+                    break;
+                }
+                if !(frame.is_text() || frame.is_binary()) {
+                    continue;
+                }
+                let text = String::from_utf8_lossy(frame.as_bytes()).into_owned();
+                if forward_text(&text, &incoming_tx).is_err() {
+                    break;
+                }
+            }
+            tracing::debug!("WebSocket reader task terminated");
+        });
+
+        Self {
+            outgoing,
+            incoming_rx: Arc::new(Mutex::new(incoming_rx)),
+        }
+    }
+}
+
+/// Parses a text frame and forwards it; returns `Err` once the receiver is gone.
+fn forward_text(
+    text: &str,
+    tx: &mpsc::UnboundedSender<Result<Message, Error>>,
+) -> Result<(), ()> {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return Ok(());
+    }
+    let message = serde_json::from_str::<Message>(trimmed).map_err(|err| {
+        tracing::warn!("Failed to parse WebSocket message: {} - Raw content: {}", err, trimmed);
+        Error::Serialization(format!("{} - Raw: {}", err, trimmed))
+    });
+    tx.send(message).map_err(|_| ())
+}
+
+#[async_trait]
+impl Transport for WebSocketTransport {
+    /// Sends a message as a single text frame.
+    async fn send(&self, message: Message) -> Result<(), Error> {
+        let json = serde_json::to_string(&message)?;
+        self.outgoing
+            .send(Outgoing::Text(json))
+            .map_err(|_| Error::Transport("WebSocket connection closed".to_string()))
+    }
+
+    /// Provides a stream of messages received as text frames.
+    fn receive(&self) -> Pin<Box<dyn Stream<Item = Result<Message, Error>> + Send>> {
+        let rx = self.incoming_rx.clone();
+        Box::pin(async_stream::stream! {
+            let mut rx = rx.lock().await;
+            while let Some(msg) = rx.recv().await {
+                yield msg;
+            }
+        })
+    }
+
+    /// Sends a close frame to the peer.
+    async fn close(&self) -> Result<(), Error> {
+        let _ = self.outgoing.send(Outgoing::Close);
+        Ok(())
+    }
+}
+
+/// Accepts WebSocket connections on `/mcp`, yielding one transport per connection.
+pub struct WebSocketListener {
+    /// Address the listener is bound to.
+    local_addr: SocketAddr,
+    /// Transports for connections that have completed the upgrade.
+    connections: Mutex<mpsc::UnboundedReceiver<WebSocketTransport>>,
+    /// Flipped to `true` on close to stop the listener.
+    closed: watch::Sender<bool>,
+}
+
+impl WebSocketListener {
+    /// Binds a WebSocket listener on `addr`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the address cannot be bound.
+    pub async fn bind(addr: impl Into<SocketAddr>) -> Result<Self, Error> {
+        let addr: SocketAddr = addr.into();
+        let (connections_tx, connections) = mpsc::unbounded_channel();
+        let (closed, mut closed_rx) = watch::channel(false);
+
+        let routes = warp::path(DEFAULT_ENDPOINT_PATH)
+            .and(warp::path::end())
+            .and(warp::ws())
+            .map(move |ws: warp::ws::Ws| {
+                let connections_tx = connections_tx.clone();
+                ws.on_upgrade(move |socket| async move {
+                    tracing::debug!("Accepted WebSocket connection");
+                    let _ = connections_tx.send(WebSocketTransport::from_warp(socket));
+                })
+            });
+
+        let (local_addr, server) = warp::serve(routes)
+            .try_bind_with_graceful_shutdown(addr, async move {
+                let _ = closed_rx.wait_for(|closed| *closed).await;
+            })
+            .map_err(|e| Error::Transport(e.to_string()))?;
+        tokio::spawn(server);
+        tracing::info!(%local_addr, "WebSocket transport listening");
+
+        Ok(Self {
+            local_addr,
+            connections: Mutex::new(connections),
+            closed,
+        })
+    }
+
+    /// Returns the address the listener is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Waits for the next client connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the listener has been closed.
+    pub async fn accept(&self) -> Result<WebSocketTransport, Error> {
+        self.connections
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| Error::Transport("WebSocket listener closed".to_string()))
+    }
+
+    /// Stops accepting new connections.
+    pub fn close(&self) {
+        let _ = self.closed.send(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Notification, Request, RequestId, Response};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_full_duplex_roundtrip() {
+        let listener = WebSocketListener::bind(([127, 0, 0, 1], 0)).await.unwrap();
+        let url = format!("ws://{}/{}", listener.local_addr(), DEFAULT_ENDPOINT_PATH);
+
+        let client = WebSocketTransport::connect(&url).await.unwrap();
+        let server = tokio::time::timeout(Duration::from_secs(5), listener.accept())
+            .await
+            .expect("Connection should be accepted")
+            .unwrap();
+
+        let mut server_rx = server.receive();
+        let mut client_rx = client.receive();
+
+        client
+            .send(Message::Request(Request::new("ping", None, RequestId::Number(1))))
+            .await
+            .unwrap();
+        match tokio::time::timeout(Duration::from_secs(5), server_rx.next()).await {
+            Ok(Some(Ok(Message::Request(request)))) => {
+                assert_eq!(request.method, "ping");
+                server
+                    .send(Message::Response(Response::success(
+                        request.id,
+                        Some(serde_json::json!({})),
+                    )))
+                    .await
+                    .unwrap();
+            }
+            other => panic!("Expected request, got {:?}", other),
+        }
+
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Response(response)))) => {
+                assert_eq!(response.id, RequestId::Number(1));
+            }
+            other => panic!("Expected response, got {:?}", other),
+        }
+
+        // The server can push messages at any time over the same connection.
+        server
+            .send(Message::Notification(Notification::new(
+                "notifications/tools/list_changed",
+                None,
+            )))
+            .await
+            .unwrap();
+        match tokio::time::timeout(Duration::from_secs(5), client_rx.next()).await {
+            Ok(Some(Ok(Message::Notification(notif)))) => {
+                assert_eq!(notif.method, "notifications/tools/list_changed");
+            }
+            other => panic!("Expected notification, got {:?}", other),
+        }
+
+        client.close().await.unwrap();
+        assert!(
+            tokio::time::timeout(Duration::from_secs(5), server_rx.next())
+                .await
+                .expect("Server stream should end after close")
+                .is_none()
+        );
+        listener.close();
+    }
+}
@@ -1,17 +1,27 @@
+//! MCP message types.
+//!
+//! These structs follow the MCP JSON schema (revision 2025-06-18, backwards compatible
+//! with 2024-11-05): field names are serialized in camelCase and optional fields are
+//! omitted when unset.
+
 use serde::{Deserialize, Serialize};
-use std::cmp::PartialEq;
 use std::collections::HashMap;
 
 /// A progress token, used to associate progress notifications with the original request
-pub type ProgressToken = String;
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ProgressToken {
+    String(String),
+    Number(i64),
+}
 
 /// An opaque token used to represent a cursor for pagination
 pub type Cursor = String;
 
-/// Base request metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Base request metadata, sent as `_meta` in request params
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct RequestMeta {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "progressToken", skip_serializing_if = "Option::is_none")]
     pub progress_token: Option<ProgressToken>,
 }
 
@@ -22,93 +32,238 @@ pub struct NotificationMeta {
     pub custom: Option<HashMap<String, serde_json::Value>>,
 }
 
-/// Progress information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Params of a `notifications/progress` notification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Progress {
-    /// The progress thus far
-    pub progress: u64,
+    /// The token given in the original request
+    #[serde(rename = "progressToken")]
+    pub progress_token: ProgressToken,
+    /// The progress thus far; increases with every notification
+    pub progress: f64,
     /// Optional total progress
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub total: Option<u64>,
+    pub total: Option<f64>,
     /// Optional message describing the progress
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
 }
 
+/// The sender or recipient of messages and data in a conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// Optional hints to the client about how an object is used or displayed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Annotations {
+    /// Who the object is intended for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<Vec<Role>>,
+    /// Importance from 0.0 (least) to 1.0 (most)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<f64>,
+    /// ISO 8601 timestamp of the last modification
+    #[serde(rename = "lastModified", skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
 /// Resource contents
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ResourceContents {
     Text {
         uri: String,
-        #[serde(rename = "mimeType")]
+        #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
         mime_type: Option<String>,
         text: String,
     },
     Blob {
         uri: String,
-        #[serde(rename = "mimeType")]
+        #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
         mime_type: Option<String>,
+        /// Base64-encoded binary data
         blob: String,
     },
 }
-/// A resource in the system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+
+impl ResourceContents {
+    /// The URI of the resource these contents belong to
+    pub fn uri(&self) -> &str {
+        match self {
+            ResourceContents::Text { uri, .. } | ResourceContents::Blob { uri, .. } => uri,
+        }
+    }
+}
+
+/// A resource the server is capable of reading
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Resource {
-    /// Unique identifier for the resource
+    /// URI of the resource
     pub uri: String,
-    /// Human-readable title
-    pub title: String,
+    /// Programmatic name of the resource
+    pub name: String,
+    /// Optional human-readable title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
     /// Optional description
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
-    /// Resource contents
-    pub contents: ResourceContents,
+    /// Optional MIME type
+    #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Annotations>,
+    /// Size of the raw content in bytes, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
 }
 
-/// Model preferences for completion requests
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A template describing a family of resources, e.g. `file:///{path}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceTemplate {
+    /// RFC 6570 URI template
+    #[serde(rename = "uriTemplate")]
+    pub uri_template: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// MIME type of every resource matching the template, if they share one
+    #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Annotations>,
+}
+
+/// Model preferences for sampling requests
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ModelPreferences {
-    /// Optional model name
+    /// Optional model name hints, in order of preference
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hints: Option<Vec<ModelHint>>,
+    /// How much to prioritize cost when selecting a model (0.0 - 1.0)
+    #[serde(rename = "costPriority", skip_serializing_if = "Option::is_none")]
+    pub cost_priority: Option<f64>,
+    /// How much to prioritize sampling speed (0.0 - 1.0)
+    #[serde(rename = "speedPriority", skip_serializing_if = "Option::is_none")]
+    pub speed_priority: Option<f64>,
+    /// How much to prioritize intelligence and capabilities (0.0 - 1.0)
+    #[serde(rename = "intelligencePriority", skip_serializing_if = "Option::is_none")]
+    pub intelligence_priority: Option<f64>,
+}
+
+/// A hint used for model selection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelHint {
+    /// Full or partial model name, e.g. `claude-3-5-sonnet` or `deepseek`
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub model: Option<String>,
-    /// Optional temperature (0.0 - 1.0)
+    pub name: Option<String>,
+}
+
+/// A message in a `sampling/createMessage` request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SamplingMessage {
+    pub role: Role,
+    pub content: MessageContent,
+}
+
+/// Which MCP context a server asks the client to include in a sampling request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IncludeContext {
+    None,
+    ThisServer,
+    AllServers,
+}
+
+/// Parameters of a `sampling/createMessage` request sent by a server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CreateMessageRequest {
+    pub messages: Vec<SamplingMessage>,
+    #[serde(rename = "modelPreferences", skip_serializing_if = "Option::is_none")]
+    pub model_preferences: Option<ModelPreferences>,
+    #[serde(rename = "systemPrompt", skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(rename = "includeContext", skip_serializing_if = "Option::is_none")]
+    pub include_context: Option<IncludeContext>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
-    /// Optional maximum number of tokens
+    #[serde(rename = "maxTokens")]
+    pub max_tokens: u32,
+    #[serde(rename = "stopSequences", skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub max_tokens: Option<u32>,
+    pub metadata: Option<serde_json::Value>,
 }
 
-/// A completion result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Result of a `sampling/createMessage` request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CreateMessageResult {
+    pub role: Role,
+    pub content: MessageContent,
+    /// Name of the model that generated the message
+    pub model: String,
+    /// Why sampling stopped, e.g. `endTurn`, `stopSequence` or `maxTokens`
+    #[serde(rename = "stopReason", skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+}
+
+/// Completion values offered for an argument
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Completion {
-    /// The completion text
-    pub text: String,
-    /// Optional score/confidence (0.0 - 1.0)
+    /// Suggested values, at most 100
+    pub values: Vec<String>,
+    /// Total number of available values, which may exceed `values.len()`
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub score: Option<f32>,
+    pub total: Option<u64>,
+    /// Whether more values exist beyond those returned
+    #[serde(rename = "hasMore", skip_serializing_if = "Option::is_none")]
+    pub has_more: Option<bool>,
 }
 
 /// Client implementation information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Implementation {
     /// Name of the implementation
     pub name: String,
     /// Version of the implementation
     pub version: String,
+    /// Optional human-readable display name (protocol 2025-06-18)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
 }
 
 /// Client capabilities
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ClientCapabilities {
-    /// Optional custom capabilities
+    /// Experimental, non-standard capabilities
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub custom: Option<HashMap<String, serde_json::Value>>,
+    pub experimental: Option<HashMap<String, serde_json::Value>>,
+    /// Present if the client can answer `roots/list`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roots: Option<RootsCapability>,
+    /// Present if the client can answer `sampling/createMessage`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<HashMap<String, serde_json::Value>>,
+    /// Present if the client can answer `elicitation/create` (protocol 2025-06-18)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elicitation: Option<HashMap<String, serde_json::Value>>,
+}
+
+/// Client support for roots
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RootsCapability {
+    /// Whether the client sends `notifications/roots/list_changed`
+    #[serde(rename = "listChanged", skip_serializing_if = "Option::is_none")]
+    pub list_changed: Option<bool>,
 }
 
 /// Server capabilities
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ServerCapabilities {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub experimental: Option<HashMap<String, HashMap<String, serde_json::Value>>>,
@@ -120,63 +275,138 @@ pub struct ServerCapabilities {
     pub tools: Option<ToolsCapability>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub logging: Option<LoggingCapability>,
+    /// Present if the server answers `completion/complete` (protocol 2025-03-26)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completions: Option<HashMap<String, serde_json::Value>>,
 }
 
-/// Logging levels
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Logging levels, as defined by RFC 5424 syslog severities
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum LoggingLevel {
-    Error,
-    Warn,
-    Info,
     Debug,
-    Trace,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+/// Params of a `logging/setLevel` request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetLevelRequest {
+    pub level: LoggingLevel,
+}
+
+/// Params of a `notifications/message` notification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggingMessageNotification {
+    pub level: LoggingLevel,
+    /// Optional name of the logger issuing the message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logger: Option<String>,
+    /// Any JSON-serializable data to log
+    pub data: serde_json::Value,
 }
 
 /// A prompt argument
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PromptArgument {
     pub name: String,
-    pub description: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub default_value: Option<String>,
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
 }
 
 /// A prompt definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Prompt {
-    pub id: String,
     pub name: String,
-    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub arguments: Option<Vec<PromptArgument>>,
 }
 
-/// Content types for prompt messages
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Content blocks used in prompt messages, tool results and sampling
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum MessageContent {
     #[serde(rename = "text")]
-    Text { text: String },
+    Text {
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
     #[serde(rename = "image")]
     Image {
-        uri: String,
-        alt_text: Option<String>,
+        /// Base64-encoded image data
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
     },
+    #[serde(rename = "audio")]
+    Audio {
+        /// Base64-encoded audio data
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+    /// Contents of a resource embedded in the message
     #[serde(rename = "resource")]
-    Resource { resource: Resource },
+    Resource {
+        resource: ResourceContents,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        annotations: Option<Annotations>,
+    },
+    /// A link to a resource the client may read (protocol 2025-06-18)
+    #[serde(rename = "resource_link")]
+    ResourceLink(Resource),
 }
 
 impl MessageContent {
+    /// Creates a text content block without annotations
+    pub fn text(text: impl Into<String>) -> Self {
+        MessageContent::Text {
+            text: text.into(),
+            annotations: None,
+        }
+    }
+
+    /// Returns the text of a text block
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            MessageContent::Text { text, .. } => Some(text),
+            _ => None,
+        }
+    }
+
     pub fn contains(&self, substring: &str) -> bool {
         match self {
-            MessageContent::Text { text } => text.contains(substring),
-            MessageContent::Image { alt_text, .. } => alt_text
-                .as_ref()
-                .map(|text| text.contains(substring))
-                .unwrap_or(false),
-            MessageContent::Resource { resource } => {
-                resource.title.contains(substring)
+            MessageContent::Text { text, .. } => text.contains(substring),
+            MessageContent::Image { .. } | MessageContent::Audio { .. } => false,
+            MessageContent::Resource { resource, .. } => match resource {
+                ResourceContents::Text { text, .. } => text.contains(substring),
+                ResourceContents::Blob { .. } => false,
+            },
+            MessageContent::ResourceLink(resource) => {
+                resource.name.contains(substring)
+                    || resource
+                        .title
+                        .as_ref()
+                        .map(|t| t.contains(substring))
+                        .unwrap_or(false)
                     || resource
                         .description
                         .as_ref()
@@ -187,69 +417,84 @@ impl MessageContent {
     }
 }
 
-impl PartialEq for MessageContent {
-    fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (MessageContent::Text { text: t1 }, MessageContent::Text { text: t2 }) => t1 == t2,
-            (
-                MessageContent::Image {
-                    uri: u1,
-                    alt_text: a1,
-                },
-                MessageContent::Image {
-                    uri: u2,
-                    alt_text: a2,
-                },
-            ) => u1 == u2 && a1 == a2,
-            (
-                MessageContent::Resource { resource: r1 },
-                MessageContent::Resource { resource: r2 },
-            ) => r1.uri == r2.uri,
-            _ => false,
-        }
-    }
-}
-
 /// A prompt message
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PromptMessage {
-    pub role: String,
-    pub content: Vec<MessageContent>,
+    pub role: Role,
+    pub content: MessageContent,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ListToolsResult {
     pub tools: Vec<Tool>,
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<Cursor>,
 }
 
 /// A tool definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Tool {
     pub name: String,
-    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
     #[serde(rename = "inputSchema")]
     pub input_schema: serde_json::Value,
+    /// JSON Schema of the tool's `structuredContent` (protocol 2025-06-18)
+    #[serde(rename = "outputSchema", default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<ToolAnnotations>,
 }
 
-/// Root definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Hints about a tool's behavior; clients must not rely on them for security decisions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolAnnotations {
+    /// Human-readable title of the tool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The tool does not modify its environment
+    #[serde(rename = "readOnlyHint", skip_serializing_if = "Option::is_none")]
+    pub read_only_hint: Option<bool>,
+    /// The tool may perform destructive updates (meaningful only if not read-only)
+    #[serde(rename = "destructiveHint", skip_serializing_if = "Option::is_none")]
+    pub destructive_hint: Option<bool>,
+    /// Calling the tool again with the same arguments has no additional effect
+    #[serde(rename = "idempotentHint", skip_serializing_if = "Option::is_none")]
+    pub idempotent_hint: Option<bool>,
+    /// The tool interacts with an open world of external entities
+    #[serde(rename = "openWorldHint", skip_serializing_if = "Option::is_none")]
+    pub open_world_hint: Option<bool>,
+}
+
+/// A root directory or file the server may operate on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Root {
-    pub id: String,
-    pub name: String,
-    pub description: String,
+    /// `file://` URI of the root
+    pub uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Result of a `roots/list` request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListRootsResult {
+    pub roots: Vec<Root>,
 }
 
 /// Initialize request
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct InitializeRequest {
-    pub client_info: Implementation,
+    #[serde(rename = "protocolVersion")]
+    pub protocol_version: String,
     pub capabilities: ClientCapabilities,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub protocol_version: Option<String>,
+    #[serde(rename = "clientInfo")]
+    pub client_info: Implementation,
 }
 
 /// Initialize result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct InitializeResult {
     #[serde(rename = "protocolVersion")]
     pub protocol_version: String,
@@ -257,104 +502,492 @@ pub struct InitializeResult {
     pub server_info: Implementation,
     #[serde(rename = "capabilities")]
     pub capabilities: ServerCapabilities,
+    /// Optional usage hints the server wants included in the model context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
 }
 
-/// List resources request
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ListResourcesRequest {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub root_id: Option<String>,
+/// Params of a paginated list request (`tools/list`, `resources/list`, ...)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PaginatedRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cursor: Option<Cursor>,
 }
 
+/// List resources request
+pub type ListResourcesRequest = PaginatedRequest;
+
 /// List resources result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ListResourcesResult {
     pub resources: Vec<Resource>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
     pub next_cursor: Option<Cursor>,
 }
 
-/// List prompts request
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ListPromptsRequest {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub cursor: Option<Cursor>,
+/// List resource templates result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListResourceTemplatesResult {
+    #[serde(rename = "resourceTemplates")]
+    pub resource_templates: Vec<ResourceTemplate>,
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<Cursor>,
 }
 
+/// Params of `resources/read`, `resources/subscribe` and `resources/unsubscribe`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceRequest {
+    pub uri: String,
+}
+
+/// Params of a `notifications/resources/updated` notification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceUpdatedNotification {
+    pub uri: String,
+}
+
+/// List prompts request
+pub type ListPromptsRequest = PaginatedRequest;
+
 /// List prompts result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ListPromptsResult {
     pub prompts: Vec<Prompt>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
     pub next_cursor: Option<Cursor>,
 }
 
-/// Complete request
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CompleteRequest {
-    pub prompt_id: String,
+/// Params of a `prompts/get` request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GetPromptRequest {
+    pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub arguments: Option<HashMap<String, String>>,
+}
+
+/// What a `completion/complete` request completes an argument of
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CompleteReference {
+    /// An argument of the named prompt
+    #[serde(rename = "ref/prompt")]
+    Prompt {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+    },
+    /// A variable of a resource template
+    #[serde(rename = "ref/resource")]
+    Resource { uri: String },
+}
+
+/// The argument being completed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompleteArgument {
+    pub name: String,
+    /// The value typed so far
+    pub value: String,
+}
+
+/// Additional context for a completion request (protocol 2025-06-18)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompleteContext {
+    /// Values of arguments that have already been resolved
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub model_preferences: Option<ModelPreferences>,
+    pub arguments: Option<HashMap<String, String>>,
+}
+
+/// Complete request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompleteRequest {
+    #[serde(rename = "ref")]
+    pub reference: CompleteReference,
+    pub argument: CompleteArgument,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<CompleteContext>,
 }
 
 /// Complete result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CompleteResult {
     pub completion: Completion,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct PromptsCapability {
-    #[serde(rename = "listChanged")]
+    #[serde(rename = "listChanged", skip_serializing_if = "Option::is_none")]
     pub list_changed: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ResourcesCapability {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub subscribe: Option<bool>,
-    #[serde(rename = "listChanged")]
+    #[serde(rename = "listChanged", skip_serializing_if = "Option::is_none")]
     pub list_changed: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ToolsCapability {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "listChanged")]
     pub list_changed: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GetPromptResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub messages: Vec<PromptMessage>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CallToolResult {
     pub content: Vec<MessageContent>,
+    /// Result conforming to the tool's `outputSchema` (protocol 2025-06-18)
+    #[serde(rename = "structuredContent", default, skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<serde_json::Value>,
     #[serde(rename = "isError", default)]
     pub is_error: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl CallToolResult {
+    /// Joins the text of all text blocks with newlines
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(MessageContent::as_text)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The result as JSON: `structuredContent` if the tool returned it, the text of a
+    /// single text block, or otherwise the content blocks themselves
+    pub fn to_json(&self) -> serde_json::Value {
+        if let Some(structured) = &self.structured_content {
+            return structured.clone();
+        }
+        match self.content.as_slice() {
+            [MessageContent::Text { text, .. }] => serde_json::Value::String(text.clone()),
+            content => serde_json::to_value(content).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CallToolRequest {
     pub name: String,
+    /// Arguments for the tool; an empty object when the client leaves them out
+    #[serde(default = "empty_arguments")]
     pub arguments: serde_json::Value,
+    /// Request metadata, e.g. a progress token for `notifications/progress`
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<RequestMeta>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn empty_arguments() -> serde_json::Value {
+    serde_json::Value::Object(serde_json::Map::new())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ReadResourceResult {
     pub contents: Vec<ResourceContents>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct LoggingCapability {
     // If the server supports logging, define fields as needed
     // If not returned by server, you can leave this empty
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+
+    /// Deserializes `value` as `T`, then checks that serializing it gives `value` back.
+    fn roundtrip<T: Serialize + DeserializeOwned + std::fmt::Debug>(value: serde_json::Value) -> T {
+        let parsed: T = serde_json::from_value(value.clone())
+            .unwrap_or_else(|e| panic!("Failed to parse {}: {}", value, e));
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), value);
+        parsed
+    }
+
+    /// Definitions from the official MCP schema for protocol 2025-06-18.
+    const SCHEMA: &str = include_str!("../schema/2025-06-18/schema.json");
+
+    /// Serializes `value` and checks it against the schema definition `name`.
+    fn assert_conforms(name: &str, value: impl Serialize) {
+        let value = serde_json::to_value(value).unwrap();
+        let mut schema: serde_json::Value = serde_json::from_str(SCHEMA).unwrap();
+        schema["$ref"] = json!(format!("#/definitions/{}", name));
+        let validator = jsonschema::draft7::new(&schema).unwrap();
+        let errors: Vec<String> = validator.iter_errors(&value).map(|e| e.to_string()).collect();
+        assert!(errors.is_empty(), "{} does not conform to {}: {:?}", value, name, errors);
+    }
+
+    /// Builds a request or notification with `method` around `params`.
+    fn message(method: &str, params: impl Serialize) -> serde_json::Value {
+        json!({"method": method, "params": params})
+    }
+
+    #[test]
+    fn test_jsonrpc_messages_conform_to_schema() {
+        use crate::protocol::{Notification, Request, RequestId, Response, ResponseError};
+
+        assert_conforms("JSONRPCRequest", Request::new("ping", None, RequestId::Number(1)));
+        assert_conforms("JSONRPCNotification", Notification::new("notifications/initialized", None));
+        assert_conforms("JSONRPCResponse", Response::success(RequestId::String("a".to_string()), Some(json!({}))));
+        assert_conforms(
+            "JSONRPCError",
+            Response::error(
+                RequestId::Number(2),
+                ResponseError::from(crate::error::Error::protocol(
+                    crate::error::ErrorCode::MethodNotFound,
+                    "Method not found: foo",
+                )),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_content_blocks() {
+        let content: Vec<MessageContent> = roundtrip(json!([
+            {"type": "text", "text": "hello", "annotations": {"audience": ["user"], "priority": 0.5}},
+            {"type": "image", "data": "aGVsbG8=", "mimeType": "image/png"},
+            {"type": "audio", "data": "aGVsbG8=", "mimeType": "audio/wav"},
+            {"type": "resource", "resource": {"uri": "file:///a.txt", "mimeType": "text/plain", "text": "a"}},
+            {"type": "resource", "resource": {"uri": "file:///b.bin", "blob": "AAE="}},
+            {"type": "resource_link", "uri": "file:///c.rs", "name": "c.rs", "mimeType": "text/x-rust"}
+        ]));
+        assert_eq!(content[0].as_text(), Some("hello"));
+        assert!(content[3].contains("a"));
+        assert!(matches!(&content[4], MessageContent::Resource { resource: ResourceContents::Blob { .. }, .. }));
+        assert!(content[5].contains("c.rs"));
+        for block in &content {
+            assert_conforms("ContentBlock", block);
+        }
+    }
+
+    #[test]
+    fn test_resources_and_templates() {
+        let resources: ListResourcesResult = roundtrip(json!({
+            "resources": [
+                {"uri": "file:///project/README.md", "name": "README.md"},
+                {
+                    "uri": "file:///project/logo.png",
+                    "name": "logo",
+                    "title": "Project logo",
+                    "description": "The logo",
+                    "mimeType": "image/png",
+                    "annotations": {"lastModified": "2025-01-12T15:00:58Z"},
+                    "size": 1024
+                }
+            ],
+            "nextCursor": "page-2"
+        }));
+        assert_eq!(resources.next_cursor.as_deref(), Some("page-2"));
+        assert!(resources.resources[0].title.is_none());
+        assert_conforms("ListResourcesResult", &resources);
+
+        let templates: ListResourceTemplatesResult = roundtrip(json!({
+            "resourceTemplates": [
+                {"uriTemplate": "file:///{path}", "name": "Project Files", "mimeType": "application/octet-stream"}
+            ]
+        }));
+        assert_eq!(templates.resource_templates[0].uri_template, "file:///{path}");
+        assert_conforms("ListResourceTemplatesResult", &templates);
+
+        let request: ResourceRequest = roundtrip(json!({"uri": "file:///project/README.md"}));
+        assert_conforms("ReadResourceRequest", message("resources/read", &request));
+        assert_conforms("SubscribeRequest", message("resources/subscribe", &request));
+
+        let read: ReadResourceResult = roundtrip(json!({
+            "contents": [{"uri": "file:///project/README.md", "mimeType": "text/markdown", "text": "# Hi"}]
+        }));
+        assert_eq!(read.contents[0].uri(), "file:///project/README.md");
+        assert_conforms("ReadResourceResult", &read);
+
+        let updated: ResourceUpdatedNotification = roundtrip(json!({"uri": "file:///project/README.md"}));
+        assert_conforms("ResourceUpdatedNotification", message("notifications/resources/updated", &updated));
+    }
+
+    #[test]
+    fn test_prompts() {
+        let prompts: ListPromptsResult = roundtrip(json!({
+            "prompts": [{
+                "name": "code_review",
+                "title": "Request Code Review",
+                "description": "Asks the LLM to review code",
+                "arguments": [{"name": "code", "description": "The code to review", "required": true}]
+            }, {
+                "name": "minimal"
+            }]
+        }));
+        assert!(prompts.prompts[1].description.is_none());
+        assert_conforms("ListPromptsResult", &prompts);
+
+        let request: GetPromptRequest = roundtrip(json!({"name": "code_review", "arguments": {"code": "fn main() {}"}}));
+        assert_conforms("GetPromptRequest", message("prompts/get", &request));
+
+        let prompt: GetPromptResult = roundtrip(json!({
+            "description": "Code review prompt",
+            "messages": [{"role": "user", "content": {"type": "text", "text": "Review this"}}]
+        }));
+        assert_eq!(prompt.messages[0].role, Role::User);
+        assert_conforms("GetPromptResult", &prompt);
+    }
+
+    #[test]
+    fn test_initialize_and_capabilities() {
+        let request: InitializeRequest = roundtrip(json!({
+            "protocolVersion": "2025-06-18",
+            "capabilities": {
+                "roots": {"listChanged": true},
+                "sampling": {},
+                "elicitation": {}
+            },
+            "clientInfo": {"name": "ExampleClient", "title": "Example Client", "version": "1.0.0"}
+        }));
+        assert!(request.capabilities.sampling.is_some());
+        assert_conforms("InitializeRequest", message("initialize", &request));
+
+        // Non-standard capabilities go under `experimental`.
+        let capabilities: ClientCapabilities = roundtrip(json!({"experimental": {"x-wallet": {"chains": ["eth"]}}}));
+        assert_conforms("ClientCapabilities", &capabilities);
+        assert!(capabilities.experimental.unwrap().contains_key("x-wallet"));
+
+        let result: InitializeResult = roundtrip(json!({
+            "protocolVersion": "2025-06-18",
+            "capabilities": {
+                "logging": {},
+                "prompts": {"listChanged": true},
+                "resources": {"subscribe": true, "listChanged": true},
+                "tools": {"listChanged": true},
+                "completions": {}
+            },
+            "serverInfo": {"name": "ExampleServer", "version": "1.0.0"},
+            "instructions": "Optional instructions for the client"
+        }));
+        assert_conforms("InitializeResult", &result);
+        assert_eq!(result.capabilities.resources.unwrap().subscribe, Some(true));
+    }
+
+    #[test]
+    fn test_roots_sampling_and_completion() {
+        let roots: ListRootsResult = roundtrip(json!({
+            "roots": [{"uri": "file:///home/user/projects/myproject", "name": "My Project"}]
+        }));
+        assert_eq!(roots.roots[0].name.as_deref(), Some("My Project"));
+        assert_conforms("ListRootsResult", &roots);
+
+        let sampling: CreateMessageRequest = roundtrip(json!({
+            "messages": [{"role": "user", "content": {"type": "text", "text": "What is the capital of France?"}}],
+            "modelPreferences": {"hints": [{"name": "claude-3-sonnet"}], "intelligencePriority": 0.8, "speedPriority": 0.5},
+            "systemPrompt": "You are a helpful assistant.",
+            "includeContext": "thisServer",
+            "maxTokens": 100
+        }));
+        assert_eq!(sampling.include_context, Some(IncludeContext::ThisServer));
+        assert_conforms("CreateMessageRequest", message("sampling/createMessage", &sampling));
+        let sampled: CreateMessageResult = roundtrip(json!({
+            "role": "assistant",
+            "content": {"type": "text", "text": "The capital of France is Paris."},
+            "model": "claude-3-sonnet-20240307",
+            "stopReason": "endTurn"
+        }));
+        assert_conforms("CreateMessageResult", &sampled);
+
+        let request: CompleteRequest = roundtrip(json!({
+            "ref": {"type": "ref/prompt", "name": "code_review"},
+            "argument": {"name": "language", "value": "py"},
+            "context": {"arguments": {"framework": "flask"}}
+        }));
+        assert!(matches!(request.reference, CompleteReference::Prompt { .. }));
+        assert_conforms("CompleteRequest", message("completion/complete", &request));
+        let request: CompleteRequest = roundtrip(json!({
+            "ref": {"type": "ref/resource", "uri": "file:///{path}"},
+            "argument": {"name": "path", "value": "src/"}
+        }));
+        assert_conforms("CompleteRequest", message("completion/complete", &request));
+        let completion: CompleteResult = roundtrip(json!({
+            "completion": {"values": ["python", "pytorch", "pyside"], "total": 10, "hasMore": true}
+        }));
+        assert_conforms("CompleteResult", &completion);
+    }
+
+    #[test]
+    fn test_logging_and_progress() {
+        let log: LoggingMessageNotification = roundtrip(json!({
+            "level": "warning",
+            "logger": "database",
+            "data": {"error": "Connection failed"}
+        }));
+        assert!(log.level > LoggingLevel::Info);
+        assert_conforms("LoggingMessageNotification", message("notifications/message", &log));
+        let level: SetLevelRequest = roundtrip(json!({"level": "info"}));
+        assert_conforms("SetLevelRequest", message("logging/setLevel", &level));
+
+        let progress: Progress = roundtrip(json!({
+            "progressToken": "abc123",
+            "progress": 50.0,
+            "total": 100.0,
+            "message": "Reticulating splines..."
+        }));
+        assert_eq!(progress.progress_token, ProgressToken::String("abc123".to_string()));
+        assert_conforms("ProgressNotification", message("notifications/progress", &progress));
+        let _: RequestMeta = roundtrip(json!({"progressToken": 7}));
+    }
+
+    #[test]
+    fn test_tools() {
+        let tools: ListToolsResult = roundtrip(json!({
+            "tools": [{
+                "name": "get_weather",
+                "title": "Weather Information Provider",
+                "description": "Get current weather information for a location",
+                "inputSchema": {"type": "object", "properties": {"location": {"type": "string"}}, "required": ["location"]}
+            }],
+            "nextCursor": "next-page-cursor"
+        }));
+        assert_eq!(tools.tools[0].title.as_deref(), Some("Weather Information Provider"));
+        assert_conforms("ListToolsResult", &tools);
+        let list: PaginatedRequest = roundtrip(json!({"cursor": "next-page-cursor"}));
+        assert_conforms("ListToolsRequest", message("tools/list", &list));
+
+        let tool: Tool = roundtrip(json!({
+            "name": "get_balance",
+            "inputSchema": {"type": "object"},
+            "outputSchema": {"type": "object", "properties": {"balance": {"type": "number"}}},
+            "annotations": {"readOnlyHint": true, "idempotentHint": true, "openWorldHint": true}
+        }));
+        assert_conforms("Tool", &tool);
+        let annotations = tool.annotations.unwrap();
+        assert_eq!(annotations.read_only_hint, Some(true));
+        assert!(annotations.destructive_hint.is_none());
+
+        let request: CallToolRequest = roundtrip(json!({"name": "get_weather", "arguments": {"location": "New York"}}));
+        assert_conforms("CallToolRequest", message("tools/call", &request));
+        // `arguments` is optional for tools that take none.
+        let request: CallToolRequest = serde_json::from_value(json!({"name": "get_time"})).unwrap();
+        assert_eq!(request.arguments, json!({}));
+        assert_conforms("CallToolRequest", message("tools/call", &request));
+
+        let result: CallToolResult = roundtrip(json!({
+            "content": [{"type": "text", "text": "72°F"}],
+            "isError": false
+        }));
+        assert!(!result.is_error);
+        assert_eq!(result.to_json(), json!("72°F"));
+        assert_conforms("CallToolResult", &result);
+
+        let result: CallToolResult = roundtrip(json!({
+            "content": [{"type": "text", "text": "{\"balance\": 1.5}"}],
+            "structuredContent": {"balance": 1.5},
+            "isError": false
+        }));
+        assert_eq!(result.to_json(), json!({"balance": 1.5}));
+        assert_conforms("CallToolResult", &result);
+    }
+}
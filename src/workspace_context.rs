@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::env;
 
 /// 工作区上下文trait - 提供工作区目录信息
@@ -85,7 +85,7 @@ impl SmartWorkspaceContext {
         Self { directories }
     }
 
-    fn is_project_root(path: &PathBuf) -> bool {
+    fn is_project_root(path: &Path) -> bool {
         ["Cargo.toml", "package.json", ".git", "pyproject.toml"]
             .iter()
             .any(|indicator| path.join(indicator).exists())